@@ -0,0 +1,150 @@
+use crate::{Error, RESPType, Result};
+
+/// Case command and subcommand names are converted to by [`CommandBuilder::normalize`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+/// Builder of a command, an array of bulk strings starting with the command name.
+///
+/// Names are checked when building: they may only hold ASCII letters, digits
+/// and `_`, `-`, `.`, `|` or `:`. Arguments are binary safe and kept as is.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CommandBuilder {
+    // The command name followed by its arguments.
+    parts: Vec<Vec<u8>>,
+    // Whether each part is a name.
+    is_name: Vec<bool>,
+    case: Option<Case>,
+}
+
+impl CommandBuilder {
+    pub fn new(name: &str) -> Self {
+        CommandBuilder { parts: vec![name.as_bytes().to_vec()], is_name: vec![true], case: None }
+    }
+
+    /// Append a subcommand name, such as `GETNAME` in `CLIENT GETNAME`.
+    pub fn subcommand(mut self, name: &str) -> Self {
+        self.parts.push(name.as_bytes().to_vec());
+        self.is_name.push(true);
+        self
+    }
+
+    /// Append an argument.
+    pub fn arg<A: AsRef<[u8]>>(mut self, arg: A) -> Self {
+        self.parts.push(arg.as_ref().to_vec());
+        self.is_name.push(false);
+        self
+    }
+
+    /// Convert the command and subcommand names to `case`. Names are left
+    /// untouched by default.
+    pub fn normalize(mut self, case: Case) -> Self {
+        self.case = Some(case);
+        self
+    }
+
+    /// Check the names and build the command, failing with
+    /// `Error::InvalidCommandName` on a name holding other characters than allowed.
+    pub fn build(self) -> Result<RESPType> {
+        let mut command = Vec::with_capacity(self.parts.len());
+        for (mut part, is_name) in self.parts.into_iter().zip(self.is_name) {
+            if is_name {
+                if !is_valid_name(&part) {
+                    return Err(Error::InvalidCommandName(String::from_utf8_lossy(&part).into_owned()));
+                }
+                match self.case {
+                    Some(Case::Upper) => part.make_ascii_uppercase(),
+                    Some(Case::Lower) => part.make_ascii_lowercase(),
+                    None => {}
+                }
+            }
+            command.push(RESPType::BulkString(part));
+        }
+        Ok(RESPType::Array(command))
+    }
+}
+
+/// Name of a received command, the first element of `resp` converted to ASCII
+/// uppercase for dispatching regardless of how the client cased it.
+///
+/// `None` if `resp` is not an array starting with a bulk or simple string
+/// holding a valid command name.
+pub fn normalized_command(resp: &RESPType) -> Option<String> {
+    let RESPType::Array(arr) = resp else {
+        return None;
+    };
+    let name = match arr.first()? {
+        RESPType::BulkString(b) => b.as_slice(),
+        RESPType::SimpleString(s) => s.as_bytes(),
+        _ => return None,
+    };
+    if !is_valid_name(name) {
+        return None;
+    }
+    // valid names are ASCII
+    Some(String::from_utf8_lossy(name).to_ascii_uppercase())
+}
+
+fn is_valid_name(name: &[u8]) -> bool {
+    !name.is_empty()
+        && name.iter().all(|b| b.is_ascii_alphanumeric() || b"_-.|:".contains(b))
+}
+
+#[cfg(test)]
+mod command_test {
+    use crate::command::{normalized_command, Case};
+    use crate::error::ErrorKind;
+    use crate::{cmd, Error, RESPType, Result};
+
+    #[test]
+    fn test_command_builder() -> Result<()> {
+        let command = RESPType::command("client").subcommand("setname").arg("conn-1").build()?;
+        assert_eq!(crate::to_string(&command)?, "*3\r\n$6\r\nclient\r\n$7\r\nsetname\r\n$6\r\nconn-1\r\n");
+        let command = cmd!("client", "setname").normalize(Case::Upper).build()?;
+        assert_eq!(crate::to_string(&command)?, "*2\r\n$6\r\nCLIENT\r\n$7\r\nsetname\r\n");
+        let command = RESPType::command("Json.Set").normalize(Case::Lower).build()?;
+        assert_eq!(crate::to_string(&command)?, "*1\r\n$8\r\njson.set\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_args() -> Result<()> {
+        let value = [b'a', 0xff, b'\r', b'\n'];
+        let command = cmd!("set", "key", value).normalize(Case::Upper).build()?;
+        assert_eq!(
+            command,
+            RESPType::Array(vec![
+                RESPType::BulkString(b"SET".to_vec()),
+                RESPType::BulkString(b"key".to_vec()),
+                RESPType::BulkString(value.to_vec()),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalized_command() -> Result<()> {
+        assert_eq!(normalized_command(&cmd!("set", "key", "value").build()?), Some("SET".to_owned()));
+        assert_eq!(normalized_command(&cmd!("Get", "key").build()?), Some("GET".to_owned()));
+        assert_eq!(normalized_command(&RESPType::BulkString(b"get".to_vec())), None);
+        assert_eq!(normalized_command(&RESPType::Array(vec![RESPType::Integer(1)])), None);
+        assert_eq!(normalized_command(&RESPType::Array(vec![])), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_name() -> Result<()> {
+        assert!(
+            cmd!("GET KEY").build()
+                .is_err_and(|err| matches!(err, Error::InvalidCommandName(name) if name == "GET KEY"))
+        );
+        assert!(
+            RESPType::command("CONFIG").subcommand("").build()
+                .is_err_and(|err| err.kind() == ErrorKind::InvalidCommandName)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use crate::replies::{ByteBuf, Bytes};
+use serde::de::{self, Unexpected};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A command sent by a client, read from an array of bulk strings whose
+/// first element names the command regardless of case.
+///
+/// Serializing writes the canonical form of the command: its name in
+/// uppercase followed by its arguments. Commands and options this type doesn't
+/// model, such as `SET ... PX`, are read as `Unknown` and forwarded unchanged.
+///
+/// ```
+/// use serde_resp::commands::Command;
+/// use serde_resp::{from_slice, to_vec};
+///
+/// let command: Command = from_slice(b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n")?;
+/// assert_eq!(command, Command::Set { key: b"k".to_vec(), value: b"v".to_vec(), ex: None, nx: false });
+/// assert_eq!(to_vec(&command)?, b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n");
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Command {
+    Get { key: Vec<u8> },
+    Set { key: Vec<u8>, value: Vec<u8>, ex: Option<u64>, nx: bool },
+    Del { keys: Vec<Vec<u8>> },
+    Exists { keys: Vec<Vec<u8>> },
+    Incr { key: Vec<u8> },
+    Echo { msg: Vec<u8> },
+    Ping { msg: Option<Vec<u8>> },
+    /// Any other command, with its name as sent.
+    Unknown { name: String, args: Vec<Vec<u8>> },
+}
+
+impl Command {
+    /// Read a command from its name followed by its arguments.
+    pub fn from_args(args: Vec<Vec<u8>>) -> crate::Result<Command> {
+        parse(args)
+    }
+
+    /// The name of the command followed by its arguments, as serialized.
+    pub fn to_args(&self) -> Vec<Vec<u8>> {
+        match self {
+            Command::Get { key } => vec![b"GET".to_vec(), key.clone()],
+            Command::Set { key, value, ex, nx } => {
+                let mut args = vec![b"SET".to_vec(), key.clone(), value.clone()];
+                if let Some(seconds) = ex {
+                    args.push(b"EX".to_vec());
+                    args.push(seconds.to_string().into_bytes());
+                }
+                if *nx {
+                    args.push(b"NX".to_vec());
+                }
+                args
+            }
+            Command::Del { keys } => std::iter::once(b"DEL".to_vec()).chain(keys.iter().cloned()).collect(),
+            Command::Exists { keys } => std::iter::once(b"EXISTS".to_vec()).chain(keys.iter().cloned()).collect(),
+            Command::Incr { key } => vec![b"INCR".to_vec(), key.clone()],
+            Command::Echo { msg } => vec![b"ECHO".to_vec(), msg.clone()],
+            Command::Ping { msg } => std::iter::once(b"PING".to_vec()).chain(msg.clone()).collect(),
+            Command::Unknown { name, args } => std::iter::once(name.clone().into_bytes()).chain(args.clone()).collect(),
+        }
+    }
+}
+
+// Read a command from {args}, its name first.
+fn parse<E: de::Error>(args: Vec<Vec<u8>>) -> Result<Command, E> {
+    let mut args = Args { name: String::new(), args: args.into_iter() };
+    args.name = String::from_utf8_lossy(&args.next_arg()?).into_owned();
+    let command = match args.name.to_ascii_uppercase().as_str() {
+        "GET" => Command::Get { key: args.next_arg()? },
+        "SET" => {
+            let key = args.next_arg()?;
+            let value = args.next_arg()?;
+            let rest = args.rest();
+            let (mut ex, mut nx) = (None, false);
+            let mut options = rest.iter();
+            while let Some(option) = options.next() {
+                if option.eq_ignore_ascii_case(b"EX") {
+                    let seconds = options.next().ok_or_else(|| args.wrong_arity())?;
+                    ex = Some(parse_u64(seconds)?);
+                } else if option.eq_ignore_ascii_case(b"NX") {
+                    nx = true;
+                } else {
+                    let mut all = vec![key, value];
+                    all.extend(rest);
+                    return Ok(Command::Unknown { name: args.name, args: all });
+                }
+            }
+            Command::Set { key, value, ex, nx }
+        }
+        "DEL" => Command::Del { keys: args.at_least_one()? },
+        "EXISTS" => Command::Exists { keys: args.at_least_one()? },
+        "INCR" => Command::Incr { key: args.next_arg()? },
+        "ECHO" => Command::Echo { msg: args.next_arg()? },
+        "PING" => Command::Ping { msg: args.args.next() },
+        _ => {
+            let rest = args.rest();
+            return Ok(Command::Unknown { name: args.name, args: rest });
+        }
+    };
+    args.finish()?;
+    Ok(command)
+}
+
+fn parse_u64<E: de::Error>(digits: &[u8]) -> Result<u64, E> {
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| E::invalid_value(Unexpected::Bytes(digits), &"an integer"))
+}
+
+// The arguments of a command being read.
+struct Args {
+    name: String,
+    args: std::vec::IntoIter<Vec<u8>>,
+}
+
+impl Args {
+    fn next_arg<E: de::Error>(&mut self) -> Result<Vec<u8>, E> {
+        self.args.next().ok_or_else(|| self.wrong_arity())
+    }
+
+    fn at_least_one<E: de::Error>(&mut self) -> Result<Vec<Vec<u8>>, E> {
+        let rest = self.rest();
+        if rest.is_empty() {
+            return Err(self.wrong_arity());
+        }
+        Ok(rest)
+    }
+
+    fn rest(&mut self) -> Vec<Vec<u8>> {
+        self.args.by_ref().collect()
+    }
+
+    // Check that every argument was read.
+    fn finish<E: de::Error>(mut self) -> Result<(), E> {
+        match self.args.next() {
+            Some(_) => Err(self.wrong_arity()),
+            None => Ok(()),
+        }
+    }
+
+    fn wrong_arity<E: de::Error>(&self) -> E {
+        E::custom(format!("wrong number of arguments for '{}' command", self.name.to_ascii_lowercase()))
+    }
+}
+
+impl Serialize for Command {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let args = self.to_args();
+        let mut seq = serializer.serialize_seq(Some(args.len()))?;
+        for arg in &args {
+            seq.serialize_element(&Bytes(arg))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let args = Vec::<ByteBuf>::deserialize(deserializer)?;
+        parse(args.into_iter().map(|arg| arg.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod commands_test {
+    use crate::commands::Command;
+    use crate::error::ErrorKind;
+    use crate::{encode_command_str, from_slice, to_vec, Result};
+
+    fn frame(args: &[&str]) -> Vec<u8> {
+        let mut frame = vec![];
+        encode_command_str(args, &mut frame).unwrap();
+        frame
+    }
+
+    fn read(args: &[&str]) -> Result<Command> {
+        from_slice(&frame(args))
+    }
+
+    #[test]
+    fn test_set() -> Result<()> {
+        let set = read(&["set", "k", "v"])?;
+        assert_eq!(set, Command::Set { key: b"k".to_vec(), value: b"v".to_vec(), ex: None, nx: false });
+        let set = read(&["SET", "k", "v", "nx", "Ex", "10"])?;
+        assert_eq!(set, Command::Set { key: b"k".to_vec(), value: b"v".to_vec(), ex: Some(10), nx: true });
+        assert_eq!(to_vec(&set)?, frame(&["SET", "k", "v", "EX", "10", "NX"]));
+        let px = read(&["set", "k", "v", "PX", "100"])?;
+        assert_eq!(
+            px,
+            Command::Unknown {
+                name: "set".to_owned(),
+                args: vec![b"k".to_vec(), b"v".to_vec(), b"PX".to_vec(), b"100".to_vec()],
+            }
+        );
+        assert_eq!(to_vec(&px)?, frame(&["set", "k", "v", "PX", "100"]));
+        assert!(read(&["set", "k", "v", "EX", "soon"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_commands() -> Result<()> {
+        assert_eq!(read(&["get", "k"])?, Command::Get { key: b"k".to_vec() });
+        assert_eq!(read(&["DEL", "a", "b"])?, Command::Del { keys: vec![b"a".to_vec(), b"b".to_vec()] });
+        assert_eq!(read(&["ping"])?, Command::Ping { msg: None });
+        let echo = read(&["echo", "hi"])?;
+        assert_eq!(to_vec(&echo)?, b"*2\r\n$4\r\nECHO\r\n$2\r\nhi\r\n");
+        let exists = Command::Exists { keys: vec![b"a".to_vec(), b"b".to_vec()] };
+        assert_eq!(Command::from_args(exists.to_args())?, exists);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown() -> Result<()> {
+        let unknown = read(&["Client", "SetName", "conn"])?;
+        assert_eq!(
+            unknown,
+            Command::Unknown { name: "Client".to_owned(), args: vec![b"SetName".to_vec(), b"conn".to_vec()] }
+        );
+        assert_eq!(to_vec(&unknown)?, b"*3\r\n$6\r\nClient\r\n$7\r\nSetName\r\n$4\r\nconn\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_arity() -> Result<()> {
+        let err = read(&["GET"]).unwrap_err();
+        assert!(err.kind() == ErrorKind::Message);
+        assert_eq!(err.to_string(), "wrong number of arguments for 'get' command");
+        assert!(read(&["get", "a", "b"]).is_err());
+        assert!(read(&["del"]).is_err());
+        assert!(read(&["set", "k", "v", "EX"]).is_err());
+        assert!(Command::from_args(vec![]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{ConvertError, RESPType};
+
+// An error for {resp} not being of the {expected} type.
+fn unexpected(expected: &'static str, resp: &RESPType) -> ConvertError {
+    ConvertError { index: None, expected, found: resp.type_name() }
+}
+
+/// Integers, or strings holding one, as with `RespDecode` and `Deserialize`.
+impl TryFrom<&RESPType> for i64 {
+    type Error = ConvertError;
+
+    fn try_from(resp: &RESPType) -> Result<Self, ConvertError> {
+        let text = match resp {
+            RESPType::Integer(n) => return Ok(*n),
+            RESPType::SimpleString(s) => s.as_bytes(),
+            RESPType::BulkString(b) => b.as_slice(),
+            other => return Err(unexpected("Integer", other)),
+        };
+        std::str::from_utf8(text)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| unexpected("Integer", resp))
+    }
+}
+
+impl TryFrom<RESPType> for i64 {
+    type Error = ConvertError;
+
+    fn try_from(resp: RESPType) -> Result<Self, ConvertError> {
+        i64::try_from(&resp)
+    }
+}
+
+/// Simple strings and UTF-8 bulk strings.
+impl TryFrom<RESPType> for String {
+    type Error = ConvertError;
+
+    fn try_from(resp: RESPType) -> Result<Self, ConvertError> {
+        match resp {
+            RESPType::SimpleString(s) => Ok(s),
+            RESPType::BulkString(b) => String::from_utf8(b).map_err(|err| ConvertError {
+                index: None,
+                expected: "String",
+                found: RESPType::BulkString(err.into_bytes()).type_name(),
+            }),
+            other => Err(unexpected("String", &other)),
+        }
+    }
+}
+
+impl TryFrom<&RESPType> for String {
+    type Error = ConvertError;
+
+    fn try_from(resp: &RESPType) -> Result<Self, ConvertError> {
+        match resp {
+            RESPType::SimpleString(s) => Ok(s.clone()),
+            RESPType::BulkString(b) => std::str::from_utf8(b)
+                .map(str::to_owned)
+                .map_err(|_| unexpected("String", resp)),
+            other => Err(unexpected("String", other)),
+        }
+    }
+}
+
+/// Bulk strings and simple strings.
+impl TryFrom<RESPType> for Vec<u8> {
+    type Error = ConvertError;
+
+    fn try_from(resp: RESPType) -> Result<Self, ConvertError> {
+        match resp {
+            RESPType::BulkString(b) => Ok(b),
+            RESPType::SimpleString(s) => Ok(s.into_bytes()),
+            other => Err(unexpected("BulkString", &other)),
+        }
+    }
+}
+
+impl TryFrom<&RESPType> for Vec<u8> {
+    type Error = ConvertError;
+
+    fn try_from(resp: &RESPType) -> Result<Self, ConvertError> {
+        match resp {
+            RESPType::BulkString(b) => Ok(b.clone()),
+            RESPType::SimpleString(s) => Ok(s.as_bytes().to_vec()),
+            other => Err(unexpected("BulkString", other)),
+        }
+    }
+}
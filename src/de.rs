@@ -1,494 +1,2317 @@
-use crate::{Error, RESPType, Result};
-use serde::de::{DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use crate::pair::PAIR_TOKEN;
+use crate::parser::Scanner;
+use crate::reply::REPLY_TOKEN;
+use crate::{read, Error, Limits, RESPType, RespError, RespKind, Result};
+use serde::de::value::{BorrowedStrDeserializer, CowStrDeserializer, SeqDeserializer};
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
 use serde::{de, Deserialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fmt::Formatter;
-use std::io::Read;
-
-const MAX_BULK_STRING_SIZE: usize = 512 * 1024 * 1024;
-
+use std::marker::PhantomData;
+use std::io::{BufRead, BufReader, Read};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+// Bytes a frame can start with.
+pub(crate) const TYPE_PREFIXES: &[u8] = b"+-:$*%_#(>,!=~|";
+
+// Most digits a bulk string length may have, enough for any `i64`.
+const MAX_LENGTH_DIGITS: usize = 20;
+
+// Newtype name `RESPType` deserializes through, so that this deserializer
+// can hand it the kinds serde's data model can't tell apart.
+pub(crate) const RESP_TYPE_TOKEN: &str = "$serde_resp::RESPType";
+
+/// Deserializer reading RESP data from a byte slice.
+///
+/// Bulk strings are binary safe, only simple strings and errors are required to be UTF-8.
+///
+/// Every positioned error reports the absolute byte offset, counted from the start
+/// of the original input, at which the offending token begins.
 pub struct Deserializer<'de> {
-    input: &'de str,
+    input: &'de [u8],
     offset: usize,
+    depth: usize,
+    // Offset at which the current top-level value begins.
+    frame_start: usize,
+    // Number of values parsed within the current top-level value.
+    elements: usize,
+    // Field or variant names of the struct or enum being read.
+    identifiers: &'static [&'static str],
+    // Whether the identifiers are field names.
+    reading_fields: bool,
+    // Kind of the current top-level value.
+    frame_kind: RespKind,
+    // Number of elements left in the array the current value is an element
+    // of, which a `Pair` takes its value from.
+    spare_elements: Option<usize>,
+    on_frame: Option<Box<dyn Fn(RespKind, usize) + 'de>>,
+    config: DeserializerBuilder,
 }
 
-impl<'de> Deserializer<'de> {
-    pub fn from_str(input: &'de str) -> Self {
-        Deserializer { input, offset: 0 }
-    }
+/// Limits and parsing modes of a [`Deserializer`], reusable across inputs.
+///
+/// The defaults are those of `Deserializer::from_bytes`. Each option can also
+/// be set on a `Deserializer` with the method of the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializerBuilder {
+    limits: Limits,
+    // Bulk payloads are not followed by "\r\n", as in RDB transfers.
+    no_bulk_trailing_crlf: bool,
+    report_array_overrun: bool,
+    strict_array_len: bool,
+    line_ending: LineEnding,
+    surface_error_replies: bool,
+    string_mode: StringMode,
+    case_insensitive_identifiers: bool,
+    deny_unknown_fields: bool,
+    on_duplicate_key: DuplicateKeyPolicy,
+    lossy_simple_strings: bool,
+    strict_integers: bool,
+    // Arrays longer than this are truncated, their other elements skipped.
+    array_limit: Option<usize>,
+    wide_integers: bool,
 }
 
-pub fn from_str<T>(s: & str) -> Result<T>
-where
-    T: DeserializeOwned,
-{
-    let mut de = Deserializer::from_str(s);
-    let t = T::deserialize(&mut de)?;
-    if de.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingCharacters)
-    }
+/// What to do with a key met twice in a map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Hand every pair to the target, which usually keeps the last value.
+    #[default]
+    LastWins,
+    /// Skip the pairs repeating a key.
+    FirstWins,
+    /// Fail with `Error::DuplicateKey`.
+    Error,
 }
 
-pub fn from_reader<R, T>(reader: &mut R) -> Result<T>
-where
-    R: Read,
-    T: DeserializeOwned
-{
-    let mut buf= Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let s = String::from_utf8(buf)?;
-    let mut de = Deserializer::from_str(&s);
-    let t = T::deserialize(&mut de)?;
-    if de.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingCharacters)
-    }
+/// Which line terminators are accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Only `\r\n`, as the protocol requires.
+    #[default]
+    CrLf,
+    /// Only `\n`.
+    Lf,
+    /// Either of them.
+    Auto,
 }
 
-impl<'de> Deserializer<'de> {
-    // Check the first char while not consuming it.
-    fn peek_char(&mut self) -> Result<char> {
-        self.input.chars().next().ok_or(Error::Eof)
-    }
+/// Which encodings are accepted where a string is expected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringMode {
+    /// Simple strings and bulk strings alike.
+    #[default]
+    Either,
+    /// Only simple strings, `+...`.
+    SimpleOnly,
+    /// Only bulk strings, `$...`.
+    BulkOnly,
+}
 
-    fn next_char(&mut self) -> Result<char> {
-        let ch = self.peek_char()?;
-        self.offset += ch.len_utf8();
-        self.input = &self.input[ch.len_utf8()..];
-        Ok(ch)
+impl Default for DeserializerBuilder {
+    fn default() -> Self {
+        DeserializerBuilder {
+            limits: Limits::default(),
+            no_bulk_trailing_crlf: false,
+            report_array_overrun: false,
+            strict_array_len: false,
+            line_ending: LineEnding::CrLf,
+            surface_error_replies: true,
+            string_mode: StringMode::Either,
+            case_insensitive_identifiers: false,
+            deny_unknown_fields: false,
+            on_duplicate_key: DuplicateKeyPolicy::LastWins,
+            lossy_simple_strings: false,
+            strict_integers: false,
+            array_limit: None,
+            wide_integers: false,
+        }
     }
+}
 
-    // Read {len} bytes, consume them.
-    // May cause Error::Eof.
-    fn skip(&mut self, len: usize) -> Result<&'de str> {
-        if self.input.len() < len {
-            return Err(Error::Eof);
-        }
-        let s: &'de str = &self.input[..len];
-        self.input = &self.input[len..];
-        self.offset += len;
-        Ok(s)
+impl DeserializerBuilder {
+    /// The options of `Deserializer::from_bytes`.
+    pub fn new() -> Self {
+        DeserializerBuilder::default()
     }
 
-    // Reading until meet "\r\n".
-    // Consume all reading bytes and return them.
-    // Consume "\r\n" as well, but not return.
-    // If not found "\r\n", return Error::Eof
-    fn read_to_end(&mut self) -> Result<&'de str> {
-        match self.input.find("\r\n") {
-            Some(len) => {
-                if let Some(len) = self.input[..len].find('\r') {
-                    Err(Error::UnexpectedCR(self.offset + len))
-                } else {
-                    let s = self.skip(len)?;
-                    // skip "\r\n"
-                    self.skip(2)?;
-                    Ok(s)
-                }
-            }
-            None => Err(Error::Eof),
-        }
+    /// Replace all size limits at once.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
     }
 
-    // Assume the next part is an integer and read it.
-    // Consume all the reading bytes.
-    fn parse_int(&mut self) -> Result<i64> {
-        let prefix = self.peek_char()?;
-        if prefix != ':' {
-            return Err(Error::UnexpectedSign{ found: prefix, expected: ':', pos: self.offset});
-        }
-        self.next_char()?;
-        let str = self.read_to_end()?;
-        let int = str.parse::<i64>()?;
-        Ok(int)
+    /// Set the maximum number of elements a single array may declare. Default is 1M.
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.limits.max_array_len = max_array_len;
+        self
     }
 
-    // Assume the next part is a simple string and read it.
-    // Consume all the reading bytes.
-    fn parse_simple_string(&mut self) -> Result<&'de str> {
-        let prefix = self.peek_char()?;
-        if prefix != '+' {
-            return Err(Error::UnexpectedSign{ found: prefix, expected: '+', pos: self.offset});
-        }
-        self.next_char()?;
-        self.read_to_end()
+    /// Set the maximum payload length of a bulk string. Default is 512MB, the protocol max.
+    pub fn with_max_bulk_len(mut self, max_bulk_len: usize) -> Self {
+        self.limits.max_bulk_len = max_bulk_len;
+        self
     }
 
-    // Assume the next part is an error and read it.
-    // Consume all the reading bytes.
-    fn parse_error(&mut self) -> Result<&str> {
-        let prefix = self.peek_char()?;
-        if prefix != '-' {
-            return Err(Error::UnexpectedSign{ found: prefix, expected: '-', pos: self.offset});
-        }
-        self.next_char()?;
-        self.read_to_end()
+    /// Set the maximum number of bytes one top-level value may span.
+    ///
+    /// Nested values count towards the top-level value containing them.
+    /// There is no limit by default.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.limits.max_frame_size = max_frame_size;
+        self
     }
 
-    // Assume the next part is a bulk string and read it.
-    // Consume all the reading bytes.
-    fn parse_bytes(&mut self) -> Result<Option<&'de [u8]>> {
-        let prefix = self.peek_char()?;
-        if prefix != '$' {
-            return Err(Error::UnexpectedSign{ found: prefix, expected: '$', pos: self.offset});
-        }
-        self.next_char()?;
-        let str: &'de str = self.read_to_end()?;
-        let len = str.parse::<i32>()?;
-        if len > MAX_BULK_STRING_SIZE as i32 {
-            return Err(Error::BulkStringOverflow);
-        }
-        if len < 0 {
-            return Ok(None)
-        }
-        if self.input.len() < len as usize {
-            return Err(Error::Eof);
-        }
-        let bulk_str = self.skip(len as usize)?;
+    /// Set the maximum number of values one top-level value may contain,
+    /// counting itself and every nested value. Default is 4M.
+    ///
+    /// Unlike the per-array limit this catches nested arrays that each look
+    /// small but multiply to a huge count. The count starts over with every
+    /// top-level value.
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.limits.max_elements = max_elements;
+        self
+    }
 
-        // skip "\r\n"
-        self.skip(2)?;
-        Ok(Some(bulk_str.as_bytes()))
+    /// Read bulk strings as exactly `len` payload bytes, with no `\r\n` after them.
+    ///
+    /// This is the form Redis uses to send RDB payloads during replication and
+    /// for `RESTORE`, e.g. `$<len>\r\n<RDB bytes>`. While enabled it applies to
+    /// every bulk string read.
+    pub fn no_bulk_trailing_crlf(mut self, enabled: bool) -> Self {
+        self.no_bulk_trailing_crlf = enabled;
+        self
     }
-}
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
-    type Error = Error;
+    /// Diagnose servers declaring fewer array elements than they send.
+    ///
+    /// When enabled, a top-level array directly followed by another value is
+    /// reported as `Error::ArrayOverrun` at the offset of that value, instead of
+    /// the value being left as trailing data. This can't tell an overrun from a
+    /// pipelined frame, so only enable it for input holding a single reply.
+    pub fn report_array_overrun(mut self, enabled: bool) -> Self {
+        self.report_array_overrun = enabled;
+        self
+    }
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.peek_char()? {
-            '+' => self.deserialize_str(visitor),
-            '-' => self.deserialize_string(visitor),
-            ':' => self.deserialize_i64(visitor),
-            '$' => self.deserialize_bytes(visitor),
-            '*' => self.deserialize_seq(visitor),
-            _ => Err(Error::ExpectedSign(self.offset)),
-        }
+    /// Refuse arrays with more elements than the target type asks for.
+    ///
+    /// By default the elements left over, e.g. when reading a 2-tuple from a
+    /// 3 element array, are skipped. When enabled they are reported as
+    /// `Error::UnconsumedElements` instead.
+    pub fn strict_array_len(mut self, enabled: bool) -> Self {
+        self.strict_array_len = enabled;
+        self
     }
 
-    fn deserialize_bool<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Accept a lone `\n` as well as `\r\n` at the end of lines, a shorthand
+    /// for `line_ending(LineEnding::Auto)`.
+    pub fn lenient_line_endings(mut self, enabled: bool) -> Self {
+        self.line_ending = if enabled { LineEnding::Auto } else { LineEnding::CrLf };
+        self
     }
 
-    fn deserialize_i8<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Set which line terminators are accepted. Default is `LineEnding::CrLf`.
+    ///
+    /// This applies to headers, simple strings, errors and the terminator after
+    /// a bulk payload. Bulk payloads are still read by their declared length,
+    /// so they may contain either. A lone `\n` is reported as `Error::UnexpectedLF`
+    /// unless accepted, and with `LineEnding::Lf` a `\r` in a line is reported
+    /// as `Error::UnexpectedCR`.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
     }
 
-    fn deserialize_i16<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Fail with `Error::ErrorReply` when an error reply is met where a value
+    /// of any type but `RESPType` is expected. On by default.
+    ///
+    /// When disabled, the text of error replies can be read as a string,
+    /// like the text of simple strings.
+    pub fn surface_error_replies(mut self, enabled: bool) -> Self {
+        self.surface_error_replies = enabled;
+        self
     }
 
-    fn deserialize_i32<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Restrict which string encodings are accepted for strings, identifiers
+    /// and unit variants. Default is `StringMode::Either`.
+    ///
+    /// A string in the other encoding is reported as `Error::UnexpectedSign`.
+    /// Reading into `RESPType` is not affected.
+    pub fn string_mode(mut self, mode: StringMode) -> Self {
+        self.string_mode = mode;
+        self
     }
 
-    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i64(self.parse_int()?)
+    /// Match struct field and enum variant names ignoring ASCII case, so that
+    /// `get`, `Get` and `GET` all name the variant `Get`.
+    ///
+    /// A name matching two names of the struct or enum, which then differ
+    /// only by case, is reported as an error.
+    pub fn case_insensitive_identifiers(mut self, enabled: bool) -> Self {
+        self.case_insensitive_identifiers = enabled;
+        self
     }
 
-    fn deserialize_u8<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Fail with `Error::UnknownField` on a key that isn't a field of the
+    /// struct being read, as if every struct had `#[serde(deny_unknown_fields)]`.
+    ///
+    /// Unknown keys are skipped by default. Structs with `#[serde(flatten)]`
+    /// fields are read as maps by serde, their unknown keys are never reported.
+    pub fn deny_unknown_fields(mut self, enabled: bool) -> Self {
+        self.deny_unknown_fields = enabled;
+        self
     }
 
-    fn deserialize_u16<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Set what to do with a key met twice in a map, or in a flat array read
+    /// as a map. Default is `DuplicateKeyPolicy::LastWins`.
+    ///
+    /// Keys are compared by content, so `+a` and `$1\r\na` are the same key.
+    /// Any other policy than the default costs a second pass over each key.
+    pub fn on_duplicate_key(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.on_duplicate_key = policy;
+        self
     }
 
-    fn deserialize_u32<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Replace invalid UTF-8 in simple strings with U+FFFD, the replacement
+    /// character, instead of failing with `Error::FromUtf8Error`.
+    ///
+    /// Errors and bulk strings read as text must still be valid UTF-8.
+    pub fn lossy_simple_strings(mut self, enabled: bool) -> Self {
+        self.lossy_simple_strings = enabled;
+        self
     }
 
-    fn deserialize_u64<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Refuse integer replies that Redis itself wouldn't send, such as `:007`,
+    /// `:+7` or `:-0`, with `Error::InvalidInteger`.
+    ///
+    /// Only an optional `-` followed by digits without leading zeros, or a
+    /// single `0`, is accepted then. By default anything `str::parse` accepts is.
+    pub fn strict_integers(mut self, enabled: bool) -> Self {
+        self.strict_integers = enabled;
+        self
     }
 
-    fn deserialize_f32<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Hand only the first `limit` elements of longer arrays to the target,
+    /// skipping the others, e.g. to sample huge replies for logging.
+    ///
+    /// This applies to every array at any depth. The elements skipped are
+    /// still parsed and count towards the limits, and `strict_array_len`
+    /// doesn't report them.
+    pub fn with_array_limit(mut self, limit: usize) -> Self {
+        self.array_limit = Some(limit);
+        self
     }
 
-    fn deserialize_f64<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Read integer replies above `i64::MAX` that fit in an `u64`, such as
+    /// some counters, as `RESPType::UInteger` instead of failing with
+    /// `Error::IntegerOverflow`.
+    ///
+    /// Other targets, such as an `u64` field, are handed an `u64` then.
+    pub fn wide_integers(mut self, enabled: bool) -> Self {
+        self.wide_integers = enabled;
+        self
     }
 
-    fn deserialize_char<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// Create a deserializer reading `input` with these options.
+    pub fn build<'de>(&self, input: &'de [u8]) -> Deserializer<'de> {
+        Deserializer { config: self.clone(), ..Deserializer::from_bytes(input) }
     }
 
-    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_borrowed_str(self.parse_simple_string()?)
+    // How a frame read from a stream with these options ends.
+    pub(crate) fn framing(&self) -> read::Framing {
+        read::Framing {
+            limits: self.limits,
+            line_ending: self.line_ending,
+            no_bulk_terminator: self.no_bulk_trailing_crlf,
+        }
     }
+}
 
-    // Use this to deserialize error.
-    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_string(self.parse_error()?.to_owned())
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Deserializer::from_bytes(input.as_bytes())
     }
 
-    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.parse_bytes()? {
-            Some(bytes) => visitor.visit_bytes(bytes),
-            None => visitor.visit_none()
+    /// Read from raw bytes, which need not be valid UTF-8.
+    ///
+    /// Bulk string payloads are binary safe. Only the text of simple strings
+    /// and errors, and bulk strings read as `str` or `String`, is validated.
+    pub fn from_bytes(input: &'de [u8]) -> Self {
+        Deserializer {
+            input,
+            offset: 0,
+            depth: 0,
+            frame_start: 0,
+            elements: 0,
+            identifiers: &[],
+            reading_fields: false,
+            frame_kind: RespKind::Null,
+            spare_elements: None,
+            on_frame: None,
+            config: DeserializerBuilder::default(),
         }
     }
 
-    fn deserialize_byte_buf<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::no_bulk_trailing_crlf`].
+    pub fn no_bulk_trailing_crlf(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.no_bulk_trailing_crlf(enabled), ..self }
     }
 
-    fn deserialize_option<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::with_limits`].
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Deserializer { config: self.config.with_limits(limits), ..self }
     }
 
-    fn deserialize_unit<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::report_array_overrun`].
+    pub fn report_array_overrun(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.report_array_overrun(enabled), ..self }
     }
 
-    fn deserialize_unit_struct<V>(self, _: &'static str, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::strict_array_len`].
+    pub fn strict_array_len(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.strict_array_len(enabled), ..self }
     }
 
-    fn deserialize_newtype_struct<V>(self, _: &'static str, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::lenient_line_endings`].
+    pub fn lenient_line_endings(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.lenient_line_endings(enabled), ..self }
     }
 
-    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        if self.peek_char()? == '*' {
-            self.skip(1)?;
-            let num = self.read_to_end()?.parse::<i32>()?;
-            if num == -1 {
-                return visitor.visit_none()
-            }
-            let value = visitor.visit_seq(RESPArrayAccess::new(self, num as usize))?;
-            Ok(value)
-        } else {
-            Err(Error::UnexpectedSign{pos: self.offset, found: self.peek_char()?, expected: '*'})
-        }
+    /// See [`DeserializerBuilder::line_ending`].
+    pub fn line_ending(self, line_ending: LineEnding) -> Self {
+        Deserializer { config: self.config.line_ending(line_ending), ..self }
     }
 
-    fn deserialize_tuple<V>(self, _: usize, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::surface_error_replies`].
+    pub fn surface_error_replies(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.surface_error_replies(enabled), ..self }
     }
 
-    fn deserialize_tuple_struct<V>(self, _: &'static str, _: usize, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::string_mode`].
+    pub fn string_mode(self, mode: StringMode) -> Self {
+        Deserializer { config: self.config.string_mode(mode), ..self }
     }
 
-    fn deserialize_map<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::case_insensitive_identifiers`].
+    pub fn case_insensitive_identifiers(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.case_insensitive_identifiers(enabled), ..self }
     }
 
-    fn deserialize_struct<V>(
-        self,
-        _: &'static str,
-        _: &'static [&'static str],
-        _: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::deny_unknown_fields`].
+    pub fn deny_unknown_fields(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.deny_unknown_fields(enabled), ..self }
     }
 
-    fn deserialize_enum<V>(
-        self,
-        _: &'static str,
-        _: &'static [&'static str],
-        _: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::on_duplicate_key`].
+    pub fn on_duplicate_key(self, policy: DuplicateKeyPolicy) -> Self {
+        Deserializer { config: self.config.on_duplicate_key(policy), ..self }
     }
 
-    fn deserialize_identifier<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::lossy_simple_strings`].
+    pub fn lossy_simple_strings(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.lossy_simple_strings(enabled), ..self }
     }
 
-    fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
+    /// See [`DeserializerBuilder::strict_integers`].
+    pub fn strict_integers(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.strict_integers(enabled), ..self }
     }
-}
-
-struct RESPArrayAccess<'a, 'de: 'a> {
-    de: &'a mut Deserializer<'de>,
-    remain_cnt: usize,
-}
 
-impl<'a, 'de> RESPArrayAccess<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, remain_cnt: usize) -> Self {
-        RESPArrayAccess { de, remain_cnt }
+    /// See [`DeserializerBuilder::with_array_limit`].
+    pub fn with_array_limit(self, limit: usize) -> Self {
+        Deserializer { config: self.config.with_array_limit(limit), ..self }
     }
-}
 
-impl<'de, 'a> SeqAccess<'de> for RESPArrayAccess<'a, 'de> {
-    type Error = Error;
+    /// See [`DeserializerBuilder::wide_integers`].
+    pub fn wide_integers(self, enabled: bool) -> Self {
+        Deserializer { config: self.config.wide_integers(enabled), ..self }
+    }
 
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        if self.remain_cnt == 0 {
-            return Ok(None);
-        }
-        self.remain_cnt -= 1;
-        seed.deserialize(&mut *self.de).map(Some)
+    /// See [`DeserializerBuilder::with_max_array_len`].
+    pub fn with_max_array_len(self, max_array_len: usize) -> Self {
+        Deserializer { config: self.config.with_max_array_len(max_array_len), ..self }
     }
-}
 
-struct RESPVisitor;
+    /// See [`DeserializerBuilder::with_max_bulk_len`].
+    pub fn with_max_bulk_len(self, max_bulk_len: usize) -> Self {
+        Deserializer { config: self.config.with_max_bulk_len(max_bulk_len), ..self }
+    }
 
-impl<'de> Visitor<'de> for RESPVisitor {
-    type Value = RESPType;
+    /// See [`DeserializerBuilder::with_max_frame_size`].
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Deserializer { config: self.config.with_max_frame_size(max_frame_size), ..self }
+    }
 
-    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-        formatter.write_str("data matches Redis Simple Protocol")
+    /// See [`DeserializerBuilder::with_max_elements`].
+    pub fn with_max_elements(self, max_elements: usize) -> Self {
+        Deserializer { config: self.config.with_max_elements(max_elements), ..self }
     }
 
-    fn visit_i64<E>(self, v: i64) -> std::result::Result<Self::Value, E>
+    /// Call `hook` with the kind and encoded size of every top-level value
+    /// once it has been read, e.g. for per connection byte accounting.
+    ///
+    /// Values skipped by `recover` are not reported, nor are values that
+    /// failed to parse.
+    pub fn on_frame<F>(mut self, hook: F) -> Self
     where
-        E: de::Error,
+        F: Fn(RespKind, usize) + 'de,
     {
-        Ok(RESPType::Integer(v))
+        self.on_frame = Some(Box::new(hook));
+        self
     }
 
-    fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(RESPType::SimpleString(v.to_owned()))
+    /// Skip to the next plausible frame start after an error, returning how
+    /// many bytes were discarded.
+    ///
+    /// This is a heuristic: the input is scanned forward for a `\r\n` followed
+    /// by one of the type prefixes, and parsing resumes right after the `\r\n`.
+    /// A bulk string payload that happens to contain such a sequence will be
+    /// mistaken for a frame. Returns `Error::Eof` if no frame start is found,
+    /// in which case nothing is discarded.
+    pub fn recover(&mut self) -> Result<usize> {
+        let pos = self.input
+            .windows(3)
+            .position(|w| &w[..2] == b"\r\n" && TYPE_PREFIXES.contains(&w[2]))
+            .ok_or(Error::Eof)?;
+        let discarded = pos + 2;
+        self.input = &self.input[discarded..];
+        self.offset += discarded;
+        self.depth = 0;
+        Ok(discarded)
     }
 
-    // remember that this is used for error
-    fn visit_string<E>(self, v: String) -> std::result::Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(RESPType::Error(v))
+    /// Offset of the next byte to read, counted from the start of the input.
+    pub fn offset(&self) -> usize {
+        self.offset
     }
 
-    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(RESPType::BulkString(v.to_vec()))
+    /// The input not consumed yet, starting at `offset()`.
+    pub fn rest(&self) -> &'de [u8] {
+        self.input
     }
 
-    fn visit_none<E>(self) -> std::result::Result<Self::Value, E> where E: de::Error {
-        Ok(RESPType::None)
+    /// Give the input not consumed yet back, e.g. for a payload following
+    /// a frame in another protocol.
+    pub fn into_inner(self) -> &'de [u8] {
+        self.input
     }
 
-    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
-    where
-        A: SeqAccess<'de>,
-    {
-        let mut array: Vec<RESPType> = vec![];
-        while let Some(element) = seq.next_element()? {
-            array.push(element);
+    /// Whether all the input has been consumed.
+    pub fn is_finished(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Check that all the input has been consumed, returning
+    /// `Error::TrailingCharacters` with the offset of the first unread byte otherwise.
+    ///
+    /// Only call this once done reading, nothing prevents reading further values after it.
+    pub fn end(&self) -> Result<()> {
+        if self.is_finished() {
+            Ok(())
+        } else {
+            Err(Error::TrailingCharacters(self.offset))
         }
-        Ok(RESPType::Array(array))
     }
-}
 
-impl<'de> Deserialize<'de> for RESPType {
-    fn deserialize<D>(de: D) -> std::result::Result<Self, D::Error>
+    /// Read the frames of the input one after the other, such as the replies
+    /// to a pipeline, as an iterator of values.
+    ///
+    /// ```
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::RESPType;
+    ///
+    /// let replies = Deserializer::from_str("+OK\r\n:5\r\n").into_iter::<RESPType>();
+    /// assert_eq!(replies.collect::<serde_resp::Result<Vec<_>>>()?.len(), 2);
+    /// # Ok::<(), serde_resp::Error>(())
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, T>
     where
-        D: serde::Deserializer<'de>,
+        T: Deserialize<'de>,
     {
-        de.deserialize_any(RESPVisitor)
+        StreamDeserializer { offset: self.offset, de: self, failed: false, output: PhantomData }
     }
 }
 
-#[cfg(test)]
-mod de_test {
-    use crate::{de, Error, RESPType};
-    use crate::error::ErrorKind;
-    use crate::Result;
+/// Iterator over the values of consecutive frames, made by
+/// [`Deserializer::into_iter`].
+///
+/// It ends at the end of the input, or after yielding the error of a frame
+/// that couldn't be read, since the next frame can't be told apart from the
+/// rest of that one.
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    // Offset of the end of the last value read.
+    offset: usize,
+    failed: bool,
+    output: PhantomData<T>,
+}
 
-    #[test]
-    fn test_simple_string() -> Result<()> {
+impl<'de, T> StreamDeserializer<'de, T> {
+    /// Number of bytes read into values so far. After an error, the offset
+    /// at which the frame that failed begins.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed || self.de.is_finished() {
+            return None;
+        }
+        match T::deserialize(&mut self.de) {
+            Ok(value) => {
+                self.offset = self.de.offset;
+                Some(Ok(value))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Deserialize the frame in `s`.
+///
+/// Strings and byte slices are borrowed from `s` when the target can hold
+/// them, as `&str` or a `Cow<str>` field marked `#[serde(borrow)]` does.
+pub fn from_str<'a, T>(s: &'a str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_slice(s.as_bytes())
+}
+
+/// Deserialize the frame in the owned `s`, into a type that doesn't borrow
+/// from its input.
+pub fn from_string<T>(s: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_slice(s.as_bytes())
+}
+
+/// Deserialize the frame in `v`, borrowing from it as `from_str` does.
+pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_slice_with(v, &DeserializerBuilder::default())
+}
+
+/// Deserialize the first frame of `v`, returning it with the bytes following it.
+pub fn from_slice_partial<'a, T>(v: &'a [u8]) -> Result<(T, &'a [u8])>
+where
+    T: Deserialize<'a>,
+{
+    let mut de = Deserializer::from_bytes(v);
+    let t = T::deserialize(&mut de)?;
+    Ok((t, de.into_inner()))
+}
+
+/// Deserialize the first frame of `s`, returning it with the text following it.
+pub fn from_str_partial<'a, T>(s: &'a str) -> Result<(T, &'a str)>
+where
+    T: Deserialize<'a>,
+{
+    let (t, rest) = from_slice_partial(s.as_bytes())?;
+    // a frame always ends with a line terminator, so the rest starts on a char boundary
+    Ok((t, &s[s.len() - rest.len()..]))
+}
+
+/// Like `from_str`, with the limits and modes of `config`.
+pub fn from_str_with<'a, T>(s: &'a str, config: &DeserializerBuilder) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_slice_with(s.as_bytes(), config)
+}
+
+/// Like `from_slice`, with the limits and modes of `config`.
+pub fn from_slice_with<'a, T>(v: &'a [u8], config: &DeserializerBuilder) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut de = config.build(v);
+    let t = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(t)
+}
+
+/// Deserialize one frame from `reader`.
+///
+/// Only the bytes of that frame are consumed, so the function returns as soon
+/// as a reply has arrived on a connection that stays open, and the replies
+/// pipelined after it remain in `reader` for the next call. It fails with
+/// `Error::Eof` if `reader` ends before the frame does.
+///
+/// Header lines are read a byte at a time, bulk payloads at once. A reader
+/// that is already buffered is better read with `from_bufread`.
+///
+/// The input is read as bytes like with `from_slice`, bulk strings need not
+/// hold valid UTF-8.
+pub fn from_reader<R, T>(reader: &mut R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned
+{
+    from_reader_with(reader, &DeserializerBuilder::default())
+}
+
+/// Like `from_reader`, with the limits and modes of `config`.
+pub fn from_reader_with<R, T>(reader: &mut R, config: &DeserializerBuilder) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned
+{
+    // a buffer of one byte never holds a byte past the frame
+    let mut reader = BufReader::with_capacity(1, reader);
+    let mut buf = Vec::new();
+    read::read_frame_with(&mut reader, &mut buf, &config.framing())?;
+    from_slice_with(&buf, config)
+}
+
+/// Deserialize the first frame of `input` and return it together with
+/// the exact slice of `input` it was parsed from.
+///
+/// Anything following the first frame is left untouched, so the raw slice
+/// can be forwarded verbatim without re-serializing the value.
+pub fn from_str_with_raw(input: &str) -> Result<(RESPType, &str)> {
+    let mut de = Deserializer::from_str(input);
+    let value = RESPType::deserialize(&mut de)?;
+    Ok((value, &input[..de.offset]))
+}
+
+/// Tell the type of the frame at the start of `input` from its leading byte,
+/// without parsing the rest of it.
+///
+/// Nulls written as `$-1` or `*-1` are reported as bulk strings and arrays.
+/// Returns `Error::Eof` for empty input and `Error::ExpectedSign(0)` if the
+/// leading byte isn't a type prefix.
+pub fn peek_type(input: &[u8]) -> Result<RespKind> {
+    let prefix = *input.first().ok_or(Error::Eof)?;
+    RespKind::from_prefix(prefix).ok_or(Error::ExpectedSign(0))
+}
+
+/// Read the number of elements declared by the array at the start of `buf`,
+/// without decoding any of them.
+///
+/// Returns `None` for a null array, and fails with `Error::UnexpectedSign` if
+/// `buf` doesn't start with an array.
+pub fn array_len(buf: &[u8]) -> Result<Option<i64>> {
+    let mut de = Deserializer::from_bytes(buf);
+    de.expect_prefix(b'*')?;
+    let len = de.parse_number::<i64>()?;
+    Ok((len >= 0).then_some(len))
+}
+
+/// Deserialize exactly one frame from a buffered reader.
+///
+/// As with `from_reader`, only the bytes of the first frame are consumed,
+/// anything after it stays in the reader.
+pub fn from_bufread<R, T>(reader: &mut R) -> Result<T>
+where
+    R: BufRead,
+    T: DeserializeOwned
+{
+    let mut buf = Vec::new();
+    read::read_frame(reader, &mut buf)?;
+    from_slice(&buf)
+}
+
+/// Deserialize the pipelined frames of a buffered reader, at most `max` of them.
+///
+/// Returns the values read, and whether the reader still had bytes after the
+/// last of them, so an event loop can bound the work done per iteration and
+/// come back for the rest. As with `from_bufread`, only the bytes of the frames
+/// read are consumed.
+///
+/// The reader is only waited on for the first frame. After that, reading stops
+/// as soon as the `BufReader` has no more bytes buffered, so a non-blocking socket never reports
+/// `WouldBlock` once a frame has been read. A frame that fails to read or
+/// decode stops reading, and its error is returned next to the values decoded
+/// before it, which were consumed from the reader as well.
+pub fn from_reader_pipeline_bounded<R, T>(reader: &mut BufReader<R>, max: usize) -> (Vec<T>, Result<bool>)
+where
+    R: Read,
+    T: DeserializeOwned
+{
+    let mut values = Vec::new();
+    let mut buf = Vec::new();
+    match reader.fill_buf() {
+        Ok([]) => return (values, Ok(false)),
+        Ok(_) => {}
+        Err(err) => return (values, Err(err.into())),
+    }
+    while values.len() < max {
+        buf.clear();
+        let value = read::read_frame(reader, &mut buf).and_then(|_| from_slice(&buf));
+        match value {
+            Ok(value) => values.push(value),
+            Err(err) => return (values, Err(err)),
+        }
+        if reader.buffer().is_empty() {
+            return (values, Ok(false));
+        }
+    }
+    let more = !reader.buffer().is_empty();
+    (values, Ok(more))
+}
+
+impl<'de> Deserializer<'de> {
+    // Check the first byte while not consuming it.
+    fn peek_byte(&mut self) -> Result<u8> {
+        self.input.first().copied().ok_or(Error::Eof)
+    }
+
+    // Consume the type prefix of the next value, which must be {expected}.
+    // A value starting at depth 0 begins a new top-level frame.
+    fn expect_prefix(&mut self, expected: u8) -> Result<()> {
+        let found = self.peek_byte()?;
+        if found == b'-' && expected != b'-' && self.config.surface_error_replies {
+            return Err(self.parse_error_reply());
+        }
+        if found != expected {
+            return Err(Error::UnexpectedSign{
+                found: found as char,
+                expected: expected as char,
+                pos: self.offset,
+            });
+        }
+        if self.depth == 0 {
+            self.frame_start = self.offset;
+            self.frame_kind = RespKind::from_prefix(expected).unwrap_or(RespKind::Null);
+            self.elements = 0;
+        }
+        self.count_elements(1)?;
+        self.skip(1)?;
+        Ok(())
+    }
+
+    // Called once a value has been read, which completes the top-level
+    // value when not nested in another.
+    fn close_frame(&mut self) {
+        if self.depth == 0 {
+            if let Some(hook) = &self.on_frame {
+                hook(self.frame_kind, self.offset - self.frame_start);
+            }
+        }
+    }
+
+    // Go one array or map deeper, failing past the depth limit.
+    fn enter(&mut self) -> Result<()> {
+        let limit = self.config.limits.max_depth;
+        if self.depth >= limit {
+            return Err(Error::DepthLimitExceeded { limit });
+        }
+        self.depth += 1;
+        self.spare_elements = None;
+        Ok(())
+    }
+
+    // Hand the bytes encoding the next value to the visitor, which is how
+    // `Reply` reads any kind of frame.
+    fn read_encoded_frame<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut scanner = Scanner::default();
+        let end = scanner.scan(self.input, &self.config.framing())?.ok_or(Error::Eof)?;
+        if self.depth == 0 {
+            self.frame_start = self.offset;
+            self.frame_kind = RespKind::from_prefix(self.input[0]).unwrap_or(RespKind::Null);
+            self.elements = 0;
+        }
+        self.count_elements(scanner.values)?;
+        let frame = self.skip(end)?;
+        self.close_frame();
+        visitor.visit_borrowed_bytes(frame)
+    }
+
+    // Read a `Pair` from the next two elements of the array it is an element of.
+    fn read_pair<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.spare_elements.take() {
+            Some(0) => Err(de::Error::invalid_length(1, &"a key and a value")),
+            Some(spare) => {
+                let mut access = RESPArrayAccess::new(self, 2);
+                let value = visitor.visit_seq(&mut access)?;
+                let remaining = access.remain_cnt;
+                self.skip_unconsumed(remaining)?;
+                self.spare_elements = Some(spare - 1);
+                Ok(value)
+            }
+            None => Err(Error::Unsupported { what: "Pair outside of a sequence" }),
+        }
+    }
+
+    // Read {len} bytes, consume them.
+    // May cause Error::Eof, or Error::FrameTooLarge before reading past the frame limit.
+    fn skip(&mut self, len: usize) -> Result<&'de [u8]> {
+        if self.offset - self.frame_start + len > self.config.limits.max_frame_size {
+            return Err(Error::FrameTooLarge { limit: self.config.limits.max_frame_size });
+        }
+        if self.input.len() < len {
+            return Err(Error::Eof);
+        }
+        let s: &'de [u8] = &self.input[..len];
+        self.input = &self.input[len..];
+        self.offset += len;
+        Ok(s)
+    }
+
+    // Reading until meet "\r\n".
+    // Consume all reading bytes and return them.
+    // Consume "\r\n" as well, but not return.
+    // A lone '\r' or '\n' in the line is an error.
+    // If not found "\r\n", return Error::Eof
+    fn read_to_end(&mut self) -> Result<&'de [u8]> {
+        match self.input.iter().position(|&b| b == b'\r' || b == b'\n') {
+            Some(len) if self.input[len] == b'\n' && self.config.line_ending != LineEnding::CrLf => {
+                let s = self.skip(len)?;
+                // skip "\n"
+                self.skip(1)?;
+                Ok(s)
+            }
+            Some(len) if self.input[len] == b'\n' => Err(Error::UnexpectedLF(self.offset + len)),
+            Some(len) if self.config.line_ending == LineEnding::Lf => Err(Error::UnexpectedCR(self.offset + len)),
+            Some(len) => match self.input.get(len + 1) {
+                Some(b'\n') => {
+                    let s = self.skip(len)?;
+                    // skip "\r\n"
+                    self.skip(2)?;
+                    Ok(s)
+                }
+                Some(_) => Err(Error::UnexpectedCR(self.offset + len)),
+                None => Err(Error::Eof),
+            },
+            None => Err(Error::Eof),
+        }
+    }
+
+    // Same as read_to_end, but the line must be valid UTF-8.
+    fn read_str_to_end(&mut self) -> Result<&'de str> {
+        to_str(self.read_to_end()?)
+    }
+
+    // Read the rest of the line and parse it as a number.
+    // Errors point at the first byte of the number.
+    fn parse_number<T>(&mut self) -> Result<T>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        let pos = self.offset;
+        String::from_utf8_lossy(self.read_to_end()?)
+            .parse::<T>()
+            .map_err(|err| Error::ParseIntError { err, pos })
+    }
+
+    // Assume the next part is an integer and read it.
+    // Consume all the reading bytes.
+    // Integer replies are the hottest path, their digits are scanned straight
+    // from the input rather than going through `str::parse`.
+    fn parse_int(&mut self) -> Result<i64> {
+        self.expect_prefix(b':')?;
+        self.check_integer_form()?;
+        let pos = self.offset;
+        let n = scan_i64(self.read_to_end()?, pos)?;
+        self.close_frame();
+        Ok(n)
+    }
+
+    // Read an integer reply, handing one above `i64::MAX` that fits in an `u64`
+    // to the visitor as such.
+    fn visit_wide_int<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.expect_prefix(b':')?;
+        self.check_integer_form()?;
+        let pos = self.offset;
+        let (negative, magnitude) = scan_magnitude(self.read_to_end()?, pos)?;
+        self.close_frame();
+        match to_i64(negative, magnitude) {
+            Some(n) => visitor.visit_i64(n),
+            None if !negative => visitor.visit_u64(magnitude),
+            None => Err(Error::IntegerOverflow { pos }),
+        }
+    }
+
+    // In strict mode, check the number of an integer reply is in canonical form.
+    fn check_integer_form(&self) -> Result<()> {
+        if self.config.strict_integers {
+            let pos = self.offset;
+            let line = self.input.split(|&b| b == b'\r' || b == b'\n').next().unwrap_or_default();
+            let digits = line.strip_prefix(b"-").unwrap_or(line);
+            if line.starts_with(b"+") || (digits.starts_with(b"0") && line != b"0") {
+                return Err(Error::InvalidInteger { pos });
+            }
+        }
+        Ok(())
+    }
+
+    // Read an integer, a big number or a string holding an integer as a `T`,
+    // which may be wider than the `i64` of integers. None for a null string.
+    fn parse_wide_int<T>(&mut self) -> Result<Option<T>>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        let pos = self.offset + 1;
+        if self.peek_byte()? == b':' {
+            self.expect_prefix(b':')?;
+            self.check_integer_form()?;
+            let n = self.parse_number()?;
+            self.close_frame();
+            return Ok(Some(n));
+        }
+        match self.parse_str()? {
+            Some(s) => s.parse().map(Some).map_err(|err| Error::ParseIntError { err, pos }),
+            None => Ok(None),
+        }
+    }
+
+    // Assume the next part is a simple string and read it.
+    // Consume all the reading bytes.
+    fn parse_simple_string(&mut self) -> Result<Cow<'de, str>> {
+        self.expect_prefix(b'+')?;
+        let s = if self.config.lossy_simple_strings {
+            String::from_utf8_lossy(self.read_to_end()?)
+        } else {
+            Cow::Borrowed(self.read_str_to_end()?)
+        };
+        self.close_frame();
+        Ok(s)
+    }
+
+    // Assume the next part is an error and read it.
+    // Consume all the reading bytes.
+    fn parse_error(&mut self) -> Result<&'de str> {
+        self.expect_prefix(b'-')?;
+        let s = self.read_str_to_end()?;
+        self.close_frame();
+        Ok(s)
+    }
+
+    // Read an error reply where a value was expected, into the error to return.
+    fn parse_error_reply(&mut self) -> Error {
+        match self.parse_error() {
+            Ok(reply) => Error::ErrorReply(RespError::parse(reply)),
+            Err(err) => err,
+        }
+    }
+
+    // Assume the next part is a RESP3 null and read it.
+
+    fn parse_resp3_null(&mut self) -> Result<()> {
+        self.expect_prefix(b'_')?;
+        let pos = self.offset;
+        if !self.read_to_end()?.is_empty() {
+            return Err(Error::TrailingCharacters(pos));
+        }
+        self.close_frame();
+        Ok(())
+    }
+
+    // Assume the next part is a RESP3 big number and read its digits.
+    fn parse_big_number(&mut self) -> Result<&'de str> {
+        self.expect_prefix(b'(')?;
+        let digits = self.read_str_to_end()?;
+        let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+        if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(de::Error::invalid_value(Unexpected::Str(digits), &"a big number"));
+        }
+        self.close_frame();
+        Ok(digits)
+    }
+
+    // Assume the next part is a RESP3 double and read it, `inf`, `-inf` and
+    // `nan` included.
+    fn parse_double(&mut self) -> Result<f64> {
+        self.expect_prefix(b',')?;
+        let text = self.read_str_to_end()?;
+        let v = parse_f64(text)?;
+        self.close_frame();
+        Ok(v)
+    }
+
+    // Assume the next part is a RESP3 boolean and read it.
+    fn parse_bool(&mut self) -> Result<bool> {
+        self.expect_prefix(b'#')?;
+        let pos = self.offset;
+        let v = match self.read_to_end()? {
+            b"t" => true,
+            b"f" => false,
+            [b't' | b'f', ..] => return Err(Error::TrailingCharacters(pos + 1)),
+            [other, ..] => return Err(Error::UnexpectedSign { expected: 't', found: *other as char, pos }),
+            [] => return Err(de::Error::invalid_value(Unexpected::Bytes(b""), &"t or f")),
+        };
+        self.close_frame();
+        Ok(v)
+    }
+
+    // Assume the next part is a bulk string and read it.
+    // Consume all the reading bytes.
+    fn parse_bytes(&mut self) -> Result<Option<&'de [u8]>> {
+        let bytes = self.parse_bulk_frame()?;
+        self.close_frame();
+        Ok(bytes)
+    }
+
+    fn parse_bulk_frame(&mut self) -> Result<Option<&'de [u8]>> {
+        self.expect_prefix(b'$')?;
+        // Bound the work spent on an absurd length before parsing it.
+        let digits = self.input
+            .iter()
+            .skip(usize::from(self.input.first() == Some(&b'-')))
+            .take(MAX_LENGTH_DIGITS + 1)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits > MAX_LENGTH_DIGITS {
+            return Err(Error::InvalidBulkLength(self.offset));
+        }
+        let len = self.parse_number::<i64>()?;
+        if len < 0 {
+            return Ok(None)
+        }
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        if len > self.config.limits.max_bulk_len {
+            return Err(Error::BulkStringOverflow);
+        }
+        let terminator = if self.config.no_bulk_trailing_crlf { 0 } else { 2 };
+        if self.offset - self.frame_start + len + terminator > self.config.limits.max_frame_size {
+            return Err(Error::FrameTooLarge { limit: self.config.limits.max_frame_size });
+        }
+        if self.input.len() < len {
+            return Err(Error::Eof);
+        }
+        if self.config.no_bulk_trailing_crlf {
+            return self.skip(len).map(Some);
+        }
+        if self.config.line_ending != LineEnding::CrLf && self.input.get(len) == Some(&b'\n') {
+            let bulk_str = self.skip(len)?;
+            // skip "\n"
+            self.skip(1)?;
+            return Ok(Some(bulk_str));
+        }
+        if self.config.line_ending == LineEnding::Lf {
+            if self.input.len() == len {
+                return Err(Error::Eof);
+            }
+            let found = self.input.iter().position(|&b| b == b'\n').ok_or(Error::Eof)?;
+            return Err(Error::WrongSizeOfBulkString { expected: len, found, pos: self.offset + len });
+        }
+        if self.input.len() >= len + 2 && &self.input[len..len + 2] != b"\r\n" {
+            // Report the real payload size if a terminator can be found at all.
+            let found = self.input.windows(2).position(|w| w == b"\r\n").ok_or(Error::Eof)?;
+            return Err(Error::WrongSizeOfBulkString { expected: len, found, pos: self.offset + len });
+        }
+        let bulk_str = self.skip(len)?;
+
+        // skip "\r\n"
+        self.skip(2)?;
+        Ok(Some(bulk_str))
+    }
+
+    // Read a simple string, an error or a UTF-8 bulk string as text.
+    fn parse_str(&mut self) -> Result<Option<Cow<'de, str>>> {
+        let expected = match (self.peek_byte()?, self.config.string_mode) {
+            (b'$', StringMode::SimpleOnly) => Some('+'),
+            (b'+', StringMode::BulkOnly) => Some('$'),
+            _ => None,
+        };
+        if let Some(expected) = expected {
+            return Err(Error::UnexpectedSign { found: self.input[0] as char, expected, pos: self.offset });
+        }
+        match self.peek_byte()? {
+            b'+' => self.parse_simple_string().map(Some),
+            b'-' if self.config.surface_error_replies => Err(self.parse_error_reply()),
+            b'-' => self.parse_error().map(|s| Some(Cow::Borrowed(s))),
+            b'$' => self.parse_bytes()?.map(|bytes| to_str(bytes).map(Cow::Borrowed)).transpose(),
+            b'(' => self.parse_big_number().map(|s| Some(Cow::Borrowed(s))),
+            _ => Err(Error::ExpectedSign(self.offset)),
+        }
+    }
+
+    // Consume the next value if it is a null bulk string or a null array.
+    fn parse_null(&mut self) -> Result<bool> {
+        match self.input {
+            [b'$', b'-', ..] => self.parse_bytes().map(|_| true),
+            [b'*', b'-', ..] => self.parse_array_header().map(|_| true),
+            [b'_', ..] => self.parse_resp3_null().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    // Assume the next part is an array header and read it.
+    // Consume the header only, return None for a null array.
+    // The declared count is checked against the limit before any element is read.
+    // RESP3 push frames, `>`, are read as arrays.
+    fn parse_array_header(&mut self) -> Result<Option<usize>> {
+        let prefix = if self.peek_byte()? == b'>' { b'>' } else { b'*' };
+        self.expect_prefix(prefix)?;
+        let num = self.parse_number::<i64>()?;
+        if num < 0 {
+            self.close_frame();
+            return Ok(None);
+        }
+        let declared = usize::try_from(num).unwrap_or(usize::MAX);
+        self.check_declared(declared)?;
+        Ok(Some(declared))
+    }
+
+    // Assume the next part is a RESP3 map header and read it.
+    // Consume the header only, return the number of key/value pairs.
+    fn parse_map_header(&mut self) -> Result<usize> {
+        self.expect_prefix(b'%')?;
+        let pairs = self.parse_number::<usize>()?;
+        self.check_declared(pairs.saturating_mul(2))?;
+        Ok(pairs)
+    }
+
+    // Check the number of elements an aggregate declares against the limits,
+    // before any element is read.
+    fn check_declared(&self, declared: usize) -> Result<()> {
+        if declared > self.config.limits.max_array_len {
+            return Err(Error::ArrayTooLarge { declared, limit: self.config.limits.max_array_len });
+        }
+        // Fail before reading elements that would exceed the budget anyway.
+        if self.elements.saturating_add(declared) > self.config.limits.max_elements {
+            return Err(Error::TooManyElements { limit: self.config.limits.max_elements });
+        }
+        Ok(())
+    }
+
+    // Account for {count} more values in the current top-level value.
+    fn count_elements(&mut self, count: usize) -> Result<()> {
+        self.elements = self.elements.saturating_add(count);
+        if self.elements > self.config.limits.max_elements {
+            return Err(Error::TooManyElements { limit: self.config.limits.max_elements });
+        }
+        Ok(())
+    }
+
+    // Hand the {pairs} key/value pairs following a map header to the visitor.
+    fn visit_map<V>(&mut self, pairs: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.enter()?;
+        let mut access = RESPMapAccess::new(self, pairs);
+        let value = visitor.visit_map(&mut access);
+        let remaining = access.remain_pairs * 2;
+        let value = value.and_then(|value| self.skip_unconsumed(remaining).map(|_| value));
+        self.depth -= 1;
+        if value.is_ok() {
+            self.close_frame();
+        }
+        value
+    }
+
+    // Hand the {pairs} key/value pairs following a map header to the visitor
+    // as a sequence of 2 element sequences, which keeps duplicate keys.
+    fn visit_pairs<V>(&mut self, pairs: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.enter()?;
+        let mut access = RESPPairsAccess { de: self, remain_pairs: pairs };
+        let value = visitor.visit_seq(&mut access);
+        let remaining = access.remain_pairs * 2;
+        let value = value.and_then(|value| self.skip_unconsumed(remaining).map(|_| value));
+        self.depth -= 1;
+        if value.is_ok() {
+            self.close_frame();
+        }
+        value
+    }
+
+    // Skip the {remaining} elements of an array the visitor didn't ask for.
+    fn skip_unconsumed(&mut self, remaining: usize) -> Result<()> {
+        if remaining > 0 && self.config.strict_array_len {
+            return Err(Error::UnconsumedElements { remaining, pos: self.offset });
+        }
+        self.skip_elements(remaining)
+    }
+
+    // Parse and drop the next {count} values.
+    fn skip_elements(&mut self, count: usize) -> Result<()> {
+        for _ in 0..count {
+            de::IgnoredAny::deserialize(&mut *self)?;
+        }
+        Ok(())
+    }
+
+    // Dispatch on the type prefix of the next value.
+    // {raw} is set when the target is `RESPType`, which gets errors and null arrays
+    // as enum variants instead of values that look like strings and nulls.
+    fn deserialize_value<V>(&mut self, visitor: V, raw: bool) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek_byte()? {
+            b'+' => visit_cow_str(visitor, self.parse_simple_string()?),
+            b'-' if raw => visitor.visit_enum(RawFrame::Error(Cow::Borrowed(self.parse_error()?))),
+            b'-' if self.config.surface_error_replies => Err(self.parse_error_reply()),
+            b'-' => visitor.visit_borrowed_str(self.parse_error()?),
+            b':' if self.config.wide_integers => self.visit_wide_int(visitor),
+            b':' => visitor.visit_i64(self.parse_int()?),
+            b'#' => visitor.visit_bool(self.parse_bool()?),
+            b',' => visitor.visit_f64(self.parse_double()?),
+            b'(' if raw => visitor.visit_enum(RawFrame::BigNumber(Cow::Borrowed(self.parse_big_number()?))),
+            b'(' => visitor.visit_borrowed_str(self.parse_big_number()?),
+            b'$' => match self.parse_bytes()? {
+                Some(bytes) => visitor.visit_borrowed_bytes(bytes),
+                None => visitor.visit_none(),
+            },
+            b'%' => {
+                let pairs = self.parse_map_header()?;
+                self.visit_map(pairs, visitor)
+            }
+            b'*' | b'>' => match self.parse_array_header()? {
+                Some(len) => self.visit_array(len, visitor),
+                None if raw => visitor.visit_enum(RawFrame::NullArray),
+                None => visitor.visit_none(),
+            },
+            b'_' => {
+                self.parse_resp3_null()?;
+                if raw {
+                    visitor.visit_enum(RawFrame::Null)
+                } else {
+                    visitor.visit_none()
+                }
+            }
+            _ => Err(Error::ExpectedSign(self.offset)),
+        }
+    }
+
+    // Run {f} with {names} as the field or variant names identifiers are matched against.
+    fn with_identifiers<T, F>(&mut self, names: &'static [&'static str], fields: bool, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let outer = std::mem::replace(&mut self.identifiers, names);
+        let outer_fields = std::mem::replace(&mut self.reading_fields, fields);
+        let value = f(self);
+        self.identifiers = outer;
+        self.reading_fields = outer_fields;
+        value
+    }
+
+    // The name among {names} that {name} stands for. Names only differ
+    // in case when identifiers are case insensitive, a name matching two of
+    // them is an error.
+    fn resolve_identifier(&self, name: Cow<'de, str>, names: &'static [&'static str]) -> Result<Cow<'de, str>> {
+        if !self.config.case_insensitive_identifiers {
+            return Ok(name);
+        }
+        let mut matches = names.iter().filter(|n| n.eq_ignore_ascii_case(&name));
+        match (matches.next(), matches.next()) {
+            (Some(a), Some(b)) => Err(de::Error::custom(format_args!(
+                "`{}` and `{}` can't be told apart with case insensitive identifiers",
+                a, b
+            ))),
+            (Some(&n), None) => Ok(Cow::Borrowed(n)),
+            (None, _) => Ok(name),
+        }
+    }
+
+    // The encoded bytes of the next value, left unconsumed. It is skipped on a copy
+    // of this deserializer, which has its limits but doesn't count towards them.
+    fn peek_value(&self) -> Result<&'de [u8]> {
+        let mut probe = Deserializer {
+            input: self.input,
+            offset: self.offset,
+            depth: self.depth,
+            frame_start: self.frame_start,
+            elements: self.elements,
+            identifiers: &[],
+            reading_fields: false,
+            frame_kind: self.frame_kind,
+            spare_elements: None,
+            on_frame: None,
+            config: self.config.clone(),
+        };
+        de::IgnoredAny::deserialize(&mut probe)?;
+        Ok(&self.input[..probe.offset - self.offset])
+    }
+
+    // The content of the value encoded in {frame}, the payload of a string.
+    fn frame_content(&self, frame: &'de [u8]) -> &'de [u8] {
+        let line_end = frame.iter().position(|&b| b == b'\n').unwrap_or(frame.len());
+        match frame.first() {
+            Some(b'$') if line_end < frame.len() => {
+                let payload = &frame[line_end + 1..];
+                if self.config.no_bulk_trailing_crlf {
+                    return payload;
+                }
+                payload.strip_suffix(b"\r\n").or_else(|| payload.strip_suffix(b"\n")).unwrap_or(payload)
+            }
+            Some(b'+' | b':') => {
+                let line = &frame[1..line_end];
+                line.strip_suffix(b"\r").unwrap_or(line)
+            }
+            _ => frame,
+        }
+    }
+
+    // Read a RESP3 map, or a flat array of alternating keys and values.
+    fn read_map<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? == b'%' {
+            let pairs = self.parse_map_header()?;
+            return self.visit_map(pairs, visitor);
+        }
+        match self.parse_array_header()? {
+            Some(len) if len.is_multiple_of(2) => self.visit_map(len / 2, visitor),
+            Some(len) => Err(de::Error::invalid_length(len, &"an even number of elements")),
+            None => visitor.visit_none(),
+        }
+    }
+
+    // Hand the {len} elements following an array header to the visitor.
+    fn visit_array<V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.enter()?;
+        let kept = self.config.array_limit.map_or(len, |limit| len.min(limit));
+        let mut access = RESPArrayAccess::new(self, kept);
+        let value = visitor.visit_seq(&mut access);
+        let remain_cnt = access.remain_cnt;
+        let value = value.and_then(|value| self.skip_unconsumed(remain_cnt).map(|_| value));
+        let value = value.and_then(|value| self.skip_elements(len - kept).map(|_| value));
+        self.depth -= 1;
+        if self.config.report_array_overrun
+            && value.is_ok()
+            && self.depth == 0
+            && self.input.first().is_some_and(|b| TYPE_PREFIXES.contains(b))
+        {
+            return Err(Error::ArrayOverrun { declared: len, pos: self.offset });
+        }
+        if value.is_ok() {
+            self.close_frame();
+        }
+        value
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_value(visitor, false)
+    }
+
+    // Booleans are RESP3 booleans, or the integers 0 and 1 as written in RESP2.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? == b'#' {
+            return visitor.visit_bool(self.parse_bool()?);
+        }
+        match self.parse_int()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            n => Err(de::Error::invalid_value(Unexpected::Signed(n), &"0 or 1")),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    // Integers may also arrive as strings, like the numeric fields of an HGETALL reply.
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek_byte()? {
+            b'+' | b'$' => {
+                let pos = self.offset;
+                match self.parse_str()? {
+                    Some(s) => visitor.visit_i64(s.parse().map_err(|err| Error::ParseIntError { err, pos })?),
+                    None => visitor.visit_none(),
+                }
+            }
+            _ => visitor.visit_i64(self.parse_int()?),
+        }
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    // Integer replies above `i64::MAX` only fit an `u64`.
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.config.wide_integers && self.peek_byte()? == b':' {
+            return self.visit_wide_int(visitor);
+        }
+        self.deserialize_i64(visitor)
+    }
+
+    // 128 bit integers are also read from big numbers.
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_wide_int()? {
+            Some(n) => visitor.visit_i128(n),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_wide_int()? {
+            Some(n) => visitor.visit_u128(n),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    // Doubles may also arrive as integers, or as strings like the reply of
+    // ZSCORE in RESP2.
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek_byte()? {
+            b':' => visitor.visit_f64(self.parse_int()? as f64),
+            b'+' | b'$' => match self.parse_str()? {
+                Some(s) => visitor.visit_f64(parse_f64(&s)?),
+                None => visitor.visit_none(),
+            },
+            _ => self.deserialize_value(visitor, false),
+        }
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    // Simple strings, errors and bulk strings can all be read as text.
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_str()? {
+            Some(s) => visit_cow_str(visitor, s),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_bytes()? {
+            Some(bytes) => visitor.visit_borrowed_bytes(bytes),
+            None => visitor.visit_none()
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    // Null bulk strings and null arrays are None, anything else is Some.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.parse_null()? {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    // Unit is written as a null bulk string, either kind of null is accepted.
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.parse_null()? {
+            visitor.visit_unit()
+        } else {
+            Err(de::Error::invalid_type(Unexpected::Other("non-null value"), &"null"))
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == RESP_TYPE_TOKEN {
+            self.deserialize_value(visitor, true)
+        } else if name == REPLY_TOKEN {
+            self.read_encoded_frame(visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? == b'%' {
+            let pairs = self.parse_map_header()?;
+            return self.visit_pairs(pairs, visitor);
+        }
+        match self.parse_array_header()? {
+            Some(len) => self.visit_array(len, visitor),
+            None => visitor.visit_none(),
+        }
+    }
+
+    // Byte arrays such as `[u8; 20]` may also be read from a bulk string of
+    // exactly that many bytes.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? != b'$' {
+            return self.deserialize_seq(visitor);
+        }
+        match self.parse_bytes()? {
+            Some(bytes) if bytes.len() == len => visitor.visit_seq(SeqDeserializer::new(bytes.iter().copied())),
+            Some(bytes) => {
+                let expected = format!("a bulk string of {len} bytes");
+                Err(de::Error::invalid_length(bytes.len(), &expected.as_str()))
+            }
+            None => visitor.visit_none(),
+        }
+    }
+
+    // Unlike tuples, tuple structs must be read from an array of exactly as
+    // many elements as they have fields.
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == PAIR_TOKEN {
+            return self.read_pair(visitor);
+        }
+        if !matches!(self.peek_byte()?, b'*' | b'>') {
+            return self.deserialize_seq(visitor);
+        }
+        let pos = self.offset;
+        match self.parse_array_header()? {
+            Some(found) if found != len => Err(Error::WrongTupleLength { expected: len, found, pos }),
+            Some(found) => self.visit_array(found, visitor),
+            None => visitor.visit_none(),
+        }
+    }
+
+    // Maps are read from RESP3 maps, or from flat arrays of alternating keys and values.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.with_identifiers(&[], false, |de| de.read_map(visitor))
+    }
+
+    // Structs are maps keyed by field name.
+    fn deserialize_struct<V>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.with_identifiers(fields, true, |de| de.read_map(visitor))
+    }
+
+    // Unit variants are read from a string holding the variant name, other
+    // variants from an array of the variant name followed by its content.
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? != b'*' {
+            return match self.parse_str()? {
+                Some(variant) => {
+                    let variant = self.resolve_identifier(variant, variants)?;
+                    visitor.visit_enum(CowStrDeserializer::new(variant))
+                }
+                None => Err(de::Error::invalid_type(Unexpected::Unit, &"enum variant")),
+            };
+        }
+        match self.parse_array_header()? {
+            Some(len) if len > 0 => {
+                self.enter()?;
+                let value = visitor.visit_enum(RESPEnumAccess::new(self, len - 1, variants));
+                self.depth -= 1;
+                if value.is_ok() {
+                    self.close_frame();
+                }
+                value
+            }
+            _ => Err(de::Error::invalid_length(0, &"an array starting with the variant name")),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let Some(name) = self.parse_str()? else {
+            return visitor.visit_none();
+        };
+        let name = self.resolve_identifier(name, self.identifiers)?;
+        if self.config.deny_unknown_fields && self.reading_fields && !self.identifiers.contains(&&*name) {
+            return Err(de::Error::unknown_field(&name, self.identifiers));
+        }
+        visit_cow_str(visitor, name)
+    }
+
+    // Skipping an error reply is not an error.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek_byte()? == b'-' {
+            self.parse_error()?;
+            return visitor.visit_unit();
+        }
+        self.deserialize_any(visitor)
+    }
+}
+
+struct RESPArrayAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    remain_cnt: usize,
+}
+
+impl<'a, 'de> RESPArrayAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, remain_cnt: usize) -> Self {
+        RESPArrayAccess { de, remain_cnt }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for RESPArrayAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remain_cnt == 0 {
+            return Ok(None);
+        }
+        self.remain_cnt -= 1;
+        self.de.spare_elements = Some(self.remain_cnt);
+        let value = seed.deserialize(&mut *self.de)?;
+        // less if the element was a `Pair`
+        if let Some(spare) = self.de.spare_elements.take() {
+            self.remain_cnt = spare;
+        }
+        Ok(Some(value))
+    }
+}
+
+// The pairs of a RESP3 map read as a sequence.
+struct RESPPairsAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    remain_pairs: usize,
+}
+
+impl<'de, 'a> SeqAccess<'de> for RESPPairsAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remain_pairs == 0 {
+            return Ok(None);
+        }
+        self.remain_pairs -= 1;
+        seed.deserialize(PairDeserializer { de: &mut *self.de }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remain_pairs)
+    }
+}
+
+// One key/value pair of a RESP3 map, read as a 2 element sequence.
+struct PairDeserializer<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for PairDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut access = RESPArrayAccess::new(self.de, 2);
+        let value = visitor.visit_seq(&mut access)?;
+        let remaining = access.remain_cnt;
+        self.de.skip_unconsumed(remaining)?;
+        Ok(value)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+// Validate bytes read from the input as UTF-8 without copying them.
+fn to_str(bytes: &[u8]) -> Result<&str> {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(String::from_utf8(bytes.to_vec()).unwrap_err().into()),
+    }
+}
+
+// Accumulate the digits of an integer reply, with an optional sign, into an
+// `i64`. {pos} is the offset of {digits} in the input.
+fn scan_i64(digits: &[u8], pos: usize) -> Result<i64> {
+    let (negative, magnitude) = scan_magnitude(digits, pos)?;
+    to_i64(negative, magnitude).ok_or(Error::IntegerOverflow { pos })
+}
+
+// Split the digits of an integer reply into its sign, true when negative,
+// and its magnitude.
+fn scan_magnitude(digits: &[u8], pos: usize) -> Result<(bool, u64)> {
+    let (negative, skipped) = match digits.first() {
+        Some(b'-') => (true, 1),
+        Some(b'+') => (false, 1),
+        _ => (false, 0),
+    };
+    if digits.len() == skipped {
+        return Err(Error::InvalidInteger { pos });
+    }
+    let mut n: u64 = 0;
+    for (i, &b) in digits.iter().enumerate().skip(skipped) {
+        if !b.is_ascii_digit() {
+            return Err(Error::InvalidInteger { pos: pos + i });
+        }
+        n = n
+            .checked_mul(10)
+            .and_then(|n| n.checked_add(u64::from(b - b'0')))
+            .ok_or(Error::IntegerOverflow { pos })?;
+    }
+    Ok((negative, n))
+}
+
+fn to_i64(negative: bool, magnitude: u64) -> Option<i64> {
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
+// Parse the text of a double, accepting `inf`, `-inf` and `nan` in any case.
+pub(crate) fn parse_f64(text: &str) -> Result<f64> {
+    text.parse().map_err(|_| de::Error::invalid_value(Unexpected::Str(text), &"a double"))
+}
+
+// Hand a string to the visitor, borrowed from the input when it can be.
+fn visit_cow_str<'de, V>(visitor: V, s: Cow<'de, str>) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match s {
+        Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Cow::Owned(s) => visitor.visit_string(s),
+    }
+}
+
+// A frame passed to `RESPType`'s visitor as an enum variant named after its kind.
+pub(crate) enum RawFrame<'de> {
+    Error(Cow<'de, str>),
+    NullArray,
+    Null,
+    BigNumber(Cow<'de, str>),
+}
+
+impl<'de> RawFrame<'de> {
+    fn kind(&self) -> &'static str {
+        match self {
+            RawFrame::Error(_) => "Error",
+            RawFrame::NullArray => "NullArray",
+            RawFrame::Null => "Null",
+            RawFrame::BigNumber(_) => "BigNumber",
+        }
+    }
+}
+
+impl<'de> EnumAccess<'de> for RawFrame<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let kind = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.kind()))?;
+        Ok((kind, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for RawFrame<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self {
+            RawFrame::Error(s) | RawFrame::BigNumber(s) => seed.deserialize(CowStrDeserializer::new(s)),
+            RawFrame::NullArray | RawFrame::Null => {
+                Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"newtype variant"))
+            }
+        }
+    }
+
+    fn tuple_variant<V>(self, _: usize, _: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"tuple variant"))
+    }
+
+    fn struct_variant<V>(self, _: &'static [&'static str], _: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"struct variant"))
+    }
+}
+
+struct RESPMapAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    remain_pairs: usize,
+    // Content of the keys read so far, unless duplicates are let through.
+    seen: HashSet<&'de [u8]>,
+}
+
+impl<'a, 'de> RESPMapAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, remain_pairs: usize) -> Self {
+        RESPMapAccess { de, remain_pairs, seen: HashSet::new() }
+    }
+
+    // Whether the next key is a duplicate the policy wants skipped, failing
+    // if it wants duplicates reported.
+    fn skip_duplicate(&mut self) -> Result<bool> {
+        let policy = self.de.config.on_duplicate_key;
+        if policy == DuplicateKeyPolicy::LastWins {
+            return Ok(false);
+        }
+        let key = self.de.frame_content(self.de.peek_value()?);
+        if self.seen.insert(key) {
+            return Ok(false);
+        }
+        if policy == DuplicateKeyPolicy::Error {
+            let key = String::from_utf8_lossy(key).into_owned();
+            return Err(Error::DuplicateKey { key, pos: self.de.offset });
+        }
+        Ok(true)
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for RESPMapAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.remain_pairs == 0 {
+            return Ok(None);
+        }
+        while self.skip_duplicate()? {
+            self.remain_pairs -= 1;
+            // skip the key and its value
+            de::IgnoredAny::deserialize(&mut *self.de)?;
+            de::IgnoredAny::deserialize(&mut *self.de)?;
+            if self.remain_pairs == 0 {
+                return Ok(None);
+            }
+        }
+        self.remain_pairs -= 1;
+        let pos = self.de.offset;
+        seed.deserialize(&mut *self.de).map(Some).map_err(|err| match err {
+            Error::UnknownField { field, .. } => Error::UnknownField { field, pos },
+            err => err,
+        })
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remain_pairs)
+    }
+}
+
+// Content of an enum variant read from an array, {remain_cnt} elements follow the name.
+struct RESPEnumAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    remain_cnt: usize,
+    variants: &'static [&'static str],
+}
+
+impl<'a, 'de> RESPEnumAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, remain_cnt: usize, variants: &'static [&'static str]) -> Self {
+        RESPEnumAccess { de, remain_cnt, variants }
+    }
+
+    fn expect_len(&self, len: usize, exp: &str) -> Result<()> {
+        if self.remain_cnt == len {
+            Ok(())
+        } else {
+            Err(de::Error::invalid_length(self.remain_cnt, &exp))
+        }
+    }
+}
+
+impl<'de, 'a> EnumAccess<'de> for RESPEnumAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = self.de.with_identifiers(self.variants, false, |de| seed.deserialize(de))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for RESPEnumAccess<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.expect_len(0, "no elements after a unit variant")
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.expect_len(1, "one element after a newtype variant")?;
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.expect_len(len, "as many elements as the tuple variant has fields")?;
+        visitor.visit_seq(RESPArrayAccess::new(self.de, len))
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.remain_cnt.is_multiple_of(2) {
+            return Err(de::Error::invalid_length(self.remain_cnt, &"an even number of elements"));
+        }
+        let pairs = self.remain_cnt / 2;
+        self.de.with_identifiers(fields, true, |de| {
+            let mut access = RESPMapAccess::new(de, pairs);
+            let value = visitor.visit_map(&mut access)?;
+            let remaining = access.remain_pairs * 2;
+            de.skip_unconsumed(remaining)?;
+            Ok(value)
+        })
+    }
+}
+
+struct RESPVisitor;
+
+impl<'de> Visitor<'de> for RESPVisitor {
+    type Value = RESPType;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("data matches Redis Simple Protocol")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(i64::try_from(v).map_or(RESPType::UInteger(v), RESPType::Integer))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::Double(v))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::Boolean(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::SimpleString(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::SimpleString(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::BulkString(v.to_vec()))
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Self::Value, E> where E: de::Error {
+        Ok(RESPType::None)
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Self::Value, E> where E: de::Error {
+        Ok(RESPType::None)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut array: Vec<RESPType> = vec![];
+        while let Some(element) = seq.next_element()? {
+            array.push(element);
+        }
+        Ok(RESPType::Array(array))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut pairs = vec![];
+        while let Some(pair) = map.next_entry()? {
+            pairs.push(pair);
+        }
+        Ok(RESPType::Map(pairs))
+    }
+
+    // Only this crate's deserializer produces these, see `RawFrame`.
+    fn visit_enum<A>(self, data: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (kind, variant): (String, _) = data.variant()?;
+        match kind.as_str() {
+            "Error" => Ok(RESPType::Error(variant.newtype_variant()?)),
+            "NullArray" => {
+                variant.unit_variant()?;
+                Ok(RESPType::NullArray)
+            }
+            "Null" => {
+                variant.unit_variant()?;
+                Ok(RESPType::Null)
+            }
+            "BigNumber" => Ok(RESPType::BigNumber(variant.newtype_variant()?)),
+            _ => Err(de::Error::unknown_variant(&kind, &["Error", "NullArray", "Null", "BigNumber"])),
+        }
+    }
+
+    fn visit_newtype_struct<D>(self, de: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        de.deserialize_any(RESPVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for RESPType {
+    fn deserialize<D>(de: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        de.deserialize_newtype_struct(RESP_TYPE_TOKEN, RESPVisitor)
+    }
+}
+
+/// Parse exactly one frame, any byte after it is `Error::TrailingCharacters`.
+/// Use `from_slice_partial` to parse the first of several frames.
+impl TryFrom<&[u8]> for RESPType {
+    type Error = Error;
+
+    fn try_from(v: &[u8]) -> Result<Self> {
+        from_slice(v)
+    }
+}
+
+/// Parse exactly one frame, see `TryFrom<&[u8]>`.
+impl TryFrom<&str> for RESPType {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
+        from_str(s)
+    }
+}
+
+impl FromStr for RESPType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        from_str(s)
+    }
+}
+
+// Read through `RESPType`, which gets error replies as values rather than
+// as `Error::ErrorReply`.
+impl<'de> Deserialize<'de> for RespError {
+    fn deserialize<D>(de: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RESPType::deserialize(de)? {
+            RESPType::Error(reply) | RESPType::SimpleString(reply) => Ok(RespError::parse(&reply)),
+            other => Err(de::Error::invalid_type(Unexpected::Other(other.type_name()), &"an error reply")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod de_test {
+    use crate::{de, Error, Limits, Pair, RESPType, RespError, RespErrorKind, RespKind};
+    use crate::replies::{KeyValue, StreamEntry};
+    use crate::de::{DuplicateKeyPolicy, LineEnding, StringMode};
+    use crate::bulk;
+    use crate::error::ErrorKind;
+    use crate::Result;
+    use serde::Deserialize;
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io::{self, BufReader, Cursor, Read};
+
+    #[test]
+    fn test_simple_string() -> Result<()> {
         let buf = "+hello\r\n";
         let sstr: RESPType = de::from_str(buf)?;
         assert_eq!(sstr, RESPType::SimpleString("hello".to_string()));
@@ -520,25 +2343,646 @@ mod de_test {
     }
 
     #[test]
-    fn test_array() -> Result<()> {
-        let arr = "*3\r\n:32\r\n+foobar\r\n$11\r\nreally bulk\r\n";
-        let resp_arr: RESPType = de::from_str(arr)?;
-        assert_eq!(resp_arr, RESPType::Array(vec![
-            RESPType::Integer(32),
-            RESPType::SimpleString("foobar".to_owned()),
-            RESPType::BulkString("really bulk".as_bytes().to_vec()),
-        ]));
+    fn test_array() -> Result<()> {
+        let arr = "*3\r\n:32\r\n+foobar\r\n$11\r\nreally bulk\r\n";
+        let resp_arr: RESPType = de::from_str(arr)?;
+        assert_eq!(resp_arr, RESPType::Array(vec![
+            RESPType::Integer(32),
+            RESPType::SimpleString("foobar".to_owned()),
+            RESPType::BulkString("really bulk".as_bytes().to_vec()),
+        ]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_null() -> Result<()> {
+        let null_bulk_str = "$-1\r\n";
+        let null_array = "*-1\r\n";
+        let resp_null: RESPType = de::from_str(null_bulk_str)?;
+        assert_eq!(resp_null, RESPType::None);
+        let resp_null: RESPType = de::from_str(null_array)?;
+        assert_eq!(resp_null, RESPType::NullArray);
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_bulk_string() -> Result<()> {
+        let bulk_str = b"$3\r\n\xff\x00\xfe\r\n";
+        let resp_str: RESPType = de::from_slice(bulk_str)?;
+        assert_eq!(resp_str, RESPType::BulkString(vec![0xff, 0x00, 0xfe]));
+        let resp_str: RESPType = de::from_reader(&mut &bulk_str[..])?;
+        assert_eq!(resp_str, RESPType::BulkString(vec![0xff, 0x00, 0xfe]));
+        let array: RESPType = de::from_slice(b"*1\r\n$2\r\n\x80\xc3\r\n")?;
+        assert_eq!(array, RESPType::Array(vec![RESPType::BulkString(vec![0x80, 0xc3])]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_non_utf8_simple_string() -> Result<()> {
+        let simple_str = b"+\xff\r\n";
+        assert!(
+            de::from_slice::<RESPType>(simple_str)
+                .is_err_and(|err| err.kind() == ErrorKind::FromUtf8Error)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_array() -> Result<()> {
+        #[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+        struct Object {
+            sha1: [u8; 20],
+            size: i64,
+        }
+        let object = Object { sha1: *b"0123456789abcdefghij", size: 42 };
+        assert_eq!(de::from_slice::<Object>(&crate::to_vec(&object)?)?, object);
+        let frame = b"*4\r\n$4\r\nsha1\r\n$20\r\n0123456789abcdefghij\r\n$4\r\nsize\r\n:42\r\n";
+        assert_eq!(de::from_slice::<Object>(frame)?, object);
+        let short = b"*4\r\n$4\r\nsha1\r\n$19\r\n0123456789abcdefghi\r\n$4\r\nsize\r\n:42\r\n";
+        let err = de::from_slice::<Object>(short).unwrap_err();
+        assert_eq!(err.to_string(), "invalid length 19, expected a bulk string of 20 bytes");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_trailing_null() -> Result<()> {
+        type Reply = (i64, String, Option<i64>);
+        assert_eq!(de::from_str::<Reply>("*3\r\n:1\r\n+a\r\n:2\r\n")?, (1, "a".to_owned(), Some(2)));
+        for null in ["$-1", "*-1", "_"] {
+            let frame = format!("*3\r\n:1\r\n+a\r\n{null}\r\n");
+            assert_eq!(de::from_str::<Reply>(&frame)?, (1, "a".to_owned(), None));
+        }
+        let err = de::from_str::<Reply>("*2\r\n:1\r\n+a\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid length 2, expected a tuple of size 3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_integers() -> Result<()> {
+        let max = ":18446744073709551615\r\n";
+        assert!(de::from_str::<RESPType>(max).is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow));
+        let wide = de::DeserializerBuilder::new().wide_integers(true);
+        let resp = RESPType::deserialize(&mut wide.build(max.as_bytes()))?;
+        assert_eq!(resp, RESPType::UInteger(u64::MAX));
+        assert_eq!(crate::to_string(&resp)?, max);
+        assert_eq!(crate::from_value::<u64>(resp.clone())?, u64::MAX);
+        assert_eq!(crate::to_value(&resp)?, resp);
+        let mut de = wide.build(b"*3\r\n:9223372036854775807\r\n:9223372036854775808\r\n:-1\r\n");
+        assert_eq!(
+            RESPType::deserialize(&mut de)?,
+            RESPType::Array(vec![RESPType::Integer(i64::MAX), RESPType::UInteger(1 << 63), RESPType::Integer(-1)])
+        );
+        assert_eq!(de::from_str_with::<u64>(max, &wide)?, u64::MAX);
+        assert_eq!(crate::to_string(&u64::MAX)?, max);
+        assert_eq!(crate::to_value(&u64::MAX)?, resp);
+        let values = vec![u64::MAX, 1 << 63, 1];
+        assert_eq!(de::from_slice_with::<Vec<u64>>(&crate::to_vec(&values)?, &wide)?, values);
+        assert_eq!(de::from_str_with::<Vec<u64>>("*2\r\n:9223372036854775808\r\n:1\r\n", &wide)?, [1 << 63, 1]);
+        assert!(de::from_str::<u64>(max).is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow));
+        assert!(de::from_str_with::<u64>(":-1\r\n", &wide).is_err_and(|err| err.kind() == ErrorKind::Message));
+        for input in [":18446744073709551616\r\n", ":-9223372036854775809\r\n"] {
+            assert!(de::from_str_with::<u64>(input, &wide).is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow));
+            let mut de = wide.build(input.as_bytes());
+            assert!(RESPType::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_limit() -> Result<()> {
+        let array = crate::to_vec(&(0..100).collect::<Vec<i64>>())?;
+        let mut de = de::Deserializer::from_bytes(&array).with_array_limit(10);
+        assert_eq!(Vec::<i64>::deserialize(&mut de)?, (0..10).collect::<Vec<_>>());
+        assert!(de.rest().is_empty());
+        let config = de::DeserializerBuilder::new().with_array_limit(1).strict_array_len(true);
+        let nested = b"*2\r\n*2\r\n:1\r\n:2\r\n*1\r\n:3\r\n+next\r\n";
+        let mut de = config.build(nested);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::Array(vec![RESPType::Array(vec![RESPType::Integer(1)])]));
+        assert_eq!(String::deserialize(&mut de)?, "next");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_struct() -> Result<()> {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point(i64, i64);
+
+        assert_eq!(de::from_str::<Point>("*2\r\n:1\r\n:2\r\n")?, Point(1, 2));
+        assert!(
+            de::from_str::<Point>("*3\r\n:1\r\n:2\r\n:3\r\n")
+                .is_err_and(|err| matches!(err, Error::WrongTupleLength { expected: 2, found: 3, pos: 0 }))
+        );
+        assert!(de::from_str::<Point>("*1\r\n:1\r\n").is_err_and(|err| err.kind() == ErrorKind::WrongTupleLength));
+        assert_eq!(de::from_str::<Vec<Point>>("*1\r\n*2\r\n:-1\r\n:0\r\n")?, [Point(-1, 0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_borrowed_cow() -> Result<()> {
+        #[derive(Deserialize)]
+        struct Entry<'a> {
+            #[serde(borrow)]
+            name: Cow<'a, str>,
+            #[serde(borrow)]
+            data: Cow<'a, [u8]>,
+        }
+
+        let input = b"*4\r\n+name\r\n$5\r\nalice\r\n+data\r\n$2\r\n\xff\x00\r\n";
+        let entry: Entry = de::from_slice(input)?;
+        assert!(matches!(entry.name, Cow::Borrowed(name) if name.as_ptr() == input[15..].as_ptr()));
+        assert!(matches!(entry.data, Cow::Borrowed(data) if data.as_ptr() == input[33..].as_ptr()));
+        let name: &str = de::from_slice(&input[11..22])?;
+        assert_eq!(name.as_ptr(), input[15..].as_ptr());
+        // the input is gone once read
+        let name: Cow<'static, str> = de::from_reader(&mut &b"$5\r\nalice\r\n"[..])?;
+        assert!(matches!(name, Cow::Owned(name) if name == "alice"));
+        // repaired text can't be borrowed
+        let lossy = de::DeserializerBuilder::new().lossy_simple_strings(true);
+        let entry: Entry = de::from_slice_with(b"*4\r\n+name\r\n+al\xffce\r\n+data\r\n$0\r\n\r\n", &lossy)?;
+        assert!(matches!(entry.name, Cow::Owned(name) if name == "al\u{fffd}ce"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pair() -> Result<()> {
+        let fields = vec![Pair("a".to_owned(), 1), Pair("b".to_owned(), 2)];
+        let frame = crate::to_vec(&fields)?;
+        assert_eq!(de::from_slice::<Vec<Pair<String, i64>>>(&frame)?, fields);
+        let map = "%2\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n";
+        assert_eq!(de::from_str::<Vec<Pair<String, i64>>>(map)?, fields);
+        let nested = "*2\r\n*2\r\n:1\r\n:2\r\n*4\r\n:3\r\n:4\r\n:5\r\n:6\r\n";
+        assert_eq!(
+            de::from_str::<(Vec<Pair<i64, i64>>, Vec<Pair<i64, i64>>)>(nested)?,
+            (vec![Pair(1, 2)], vec![Pair(3, 4), Pair(5, 6)])
+        );
+        assert!(
+            de::from_str::<Vec<Pair<i64, i64>>>("*3\r\n:1\r\n:2\r\n:3\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::Message)
+        );
+        assert!(
+            de::from_str::<Pair<i64, i64>>(":1\r\n:2\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::Unsupported)
+        );
+
+        // the value path flattens pairs the same way
+        let value = crate::to_value(&fields)?;
+        assert_eq!(value, de::from_slice::<RESPType>(&frame)?);
+        assert_eq!(crate::from_value::<Vec<Pair<String, i64>>>(value)?, fields);
+        let value = de::from_str::<RESPType>(nested)?;
+        assert_eq!(
+            crate::from_value::<(Vec<Pair<i64, i64>>, Vec<Pair<i64, i64>>)>(value)?,
+            (vec![Pair(1, 2)], vec![Pair(3, 4), Pair(5, 6)])
+        );
+        let odd = de::from_str::<RESPType>("*3\r\n:1\r\n:2\r\n:3\r\n")?;
+        assert!(crate::from_value::<Vec<Pair<i64, i64>>>(odd).is_err_and(|err| err.kind() == ErrorKind::Message));
+        assert!(crate::to_value(&Pair(1, 2)).is_err_and(|err| err.kind() == ErrorKind::Unsupported));
+        assert!(crate::to_value(&(1, Pair(1, 2))).is_err_and(|err| err.kind() == ErrorKind::Unsupported));
+        let pairs = vec![KeyValue { key: b"k".to_vec(), value: b"v".to_vec() }];
+        let value = crate::to_value(&pairs)?;
+        assert_eq!(value, de::from_slice::<RESPType>(&crate::to_vec(&pairs)?)?);
+        assert_eq!(crate::from_value::<Vec<KeyValue>>(value)?, pairs);
+        let entries = vec![StreamEntry { id: "1-0".to_owned(), fields: vec![(b"f".to_vec(), b"v".to_vec())] }];
+        let value = crate::to_value(&entries)?;
+        assert_eq!(value, de::from_slice::<RESPType>(&crate::to_vec(&entries)?)?);
+        assert_eq!(crate::from_value::<Vec<StreamEntry>>(value)?, entries);
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_from() -> Result<()> {
+        let cases: [(&str, RESPType); 6] = [
+            ("+hello\r\n", RESPType::SimpleString("hello".to_owned())),
+            ("-Err unknown error\r\n", RESPType::Error("Err unknown error".to_owned())),
+            (":114514\r\n", RESPType::Integer(114514)),
+            ("$5\r\nhello\r\n", bulk!("hello")),
+            ("*2\r\n:1\r\n$-1\r\n", RESPType::Array(vec![RESPType::Integer(1), RESPType::None])),
+            ("*-1\r\n", RESPType::NullArray),
+        ];
+        for (frame, expected) in cases {
+            assert_eq!(RESPType::try_from(frame)?, expected);
+            assert_eq!(RESPType::try_from(frame.as_bytes())?, expected);
+            assert_eq!(frame.parse::<RESPType>()?, expected);
+        }
+        let buf = b"$3\r\n\xff\x00\xfe\r\n".to_vec();
+        let value: RESPType = buf.as_slice().try_into()?;
+        assert_eq!(value, RESPType::BulkString(vec![0xff, 0x00, 0xfe]));
+        assert!(RESPType::try_from("+OK\r\n:1\r\n").is_err_and(|err| matches!(err, Error::TrailingCharacters(5))));
+        assert!(RESPType::try_from(&b"+OK\r"[..]).is_err_and(|err| err.kind() == ErrorKind::Eof));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bytes() -> Result<()> {
+        let input = b"+OK\r\n-ERR no\r\n:-3\r\n$4\r\n\x00\xff\r\n\r\n*1\r\n:1\r\n$-1\r\n*-1\r\n_\r\n(12\r\n%1\r\n+k\r\n:2\r\n";
+        let mut de = de::Deserializer::from_bytes(input);
+        let mut read = || RESPType::deserialize(&mut de);
+        assert_eq!(read()?, RESPType::SimpleString("OK".to_owned()));
+        assert_eq!(read()?, RESPType::Error("ERR no".to_owned()));
+        assert_eq!(read()?, RESPType::Integer(-3));
+        assert_eq!(read()?, RESPType::BulkString(b"\x00\xff\r\n".to_vec()));
+        assert_eq!(read()?, RESPType::Array(vec![RESPType::Integer(1)]));
+        assert_eq!(read()?, RESPType::None);
+        assert_eq!(read()?, RESPType::NullArray);
+        assert_eq!(read()?, RESPType::Null);
+        assert_eq!(read()?, RESPType::BigNumber("12".to_owned()));
+        assert_eq!(read()?, RESPType::Map(vec![(RESPType::SimpleString("k".to_owned()), RESPType::Integer(2))]));
+        assert!(read().is_err_and(|err| err.kind() == ErrorKind::Eof));
+        let mut de = de::Deserializer::from_bytes(b"$3\r\n\xfe\x01\x02\r\n$3\r\n\xfe\x01\x02\r\n");
+        assert_eq!(de::Deserializer::parse_bytes(&mut de)?, Some(&b"\xfe\x01\x02"[..]));
+        assert!(String::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::FromUtf8Error));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_string() -> Result<()> {
+        fn read_owned(frame: String) -> Result<Vec<String>> {
+            de::from_string(frame)
+        }
+
+        let frame = String::from("*2\r\n+a\r\n$2\r\nbc\r\n");
+        assert_eq!(read_owned(frame)?, ["a", "bc"]);
+        assert!(de::from_string::<i64>(":1\r\n:2\r\n".to_owned()).is_err_and(|err| matches!(err, Error::TrailingCharacters(4))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rest() -> Result<()> {
+        let input = b"*2\r\n:1\r\n:2\r\n$5\r\nhello\r\n";
+        let mut de = de::Deserializer::from_bytes(input);
+        assert_eq!(Vec::<i64>::deserialize(&mut de)?, [1, 2]);
+        assert_eq!(de.offset(), 12);
+        assert_eq!(de.rest(), &input[12..]);
+        let rest = de.into_inner();
+        assert_eq!(de::from_slice::<String>(rest)?, "hello");
+
+        let (first, rest) = de::from_str_partial::<String>("+a\r\n+b\r\n")?;
+        assert_eq!((first.as_str(), rest), ("a", "+b\r\n"));
+        let (last, rest) = de::from_slice_partial::<String>(rest.as_bytes())?;
+        assert_eq!((last.as_str(), rest), ("b", &b""[..]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lossy_simple_strings() -> Result<()> {
+        let input = b"+ab\xffc\r\n+ok\r\n-ERR \xff\r\n";
+        let mut de = de::Deserializer::from_bytes(input).lossy_simple_strings(true);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::SimpleString("ab\u{fffd}c".to_owned()));
+        assert_eq!(String::deserialize(&mut de)?, "ok");
+        assert!(RESPType::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::FromUtf8Error));
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_integers() -> Result<()> {
+        assert_eq!(de::from_str::<i64>(":007\r\n")?, 7);
+        let mut de = de::Deserializer::from_str(":007\r\n").strict_integers(true);
+        assert!(i64::deserialize(&mut de).is_err_and(|err| matches!(err, Error::InvalidInteger { pos: 1 })));
+        let strict = de::DeserializerBuilder::new().strict_integers(true);
+        for input in [":-07\r\n", ":-0\r\n", ":+7\r\n", ":00\r\n"] {
+            let mut de = strict.build(input.as_bytes());
+            assert!(RESPType::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::InvalidInteger));
+        }
+        let mut de = strict.build(b"*3\r\n:0\r\n:-70\r\n:100\r\n");
+        assert_eq!(Vec::<i64>::deserialize(&mut de)?, [0, -70, 100]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_unexpected_lf() -> Result<()> {
+        let simple_str = "+\n\r\n";
+        assert!(
+            de::from_str::<RESPType>(simple_str)
+                .is_err_and(|err| matches!(err, Error::UnexpectedLF(1)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_option() -> Result<()> {
+        let mget = "*3\r\n$1\r\na\r\n$-1\r\n$1\r\nc\r\n";
+        let values: Vec<Option<String>> = de::from_str(mget)?;
+        assert_eq!(values, vec![Some("a".to_owned()), None, Some("c".to_owned())]);
+        let values: Option<Vec<i64>> = de::from_str("*-1\r\n")?;
+        assert_eq!(values, None);
+        let value: Option<String> = de::from_str("+OK\r\n")?;
+        assert_eq!(value, Some("OK".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() -> Result<()> {
+        let hgetall = "*4\r\n$3\r\nfoo\r\n$1\r\n1\r\n$3\r\nbar\r\n$1\r\n2\r\n";
+        let map: HashMap<String, String> = de::from_str(hgetall)?;
+        assert_eq!(map, HashMap::from([
+            ("foo".to_owned(), "1".to_owned()),
+            ("bar".to_owned(), "2".to_owned()),
+        ]));
+        assert!(de::from_str::<HashMap<String, String>>("*1\r\n+foo\r\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_mode() -> Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Entry {
+            name: String,
+        }
+
+        let simple = "*2\r\n+name\r\n+foo\r\n";
+        let bulk = "*2\r\n$4\r\nname\r\n$3\r\nfoo\r\n";
+        let expected = Entry { name: "foo".to_owned() };
+        let either = de::DeserializerBuilder::new();
+        assert_eq!(de::from_str_with::<Entry>(simple, &either)?, expected);
+        assert_eq!(de::from_str_with::<Entry>(bulk, &either)?, expected);
+        let simple_only = de::DeserializerBuilder::new().string_mode(StringMode::SimpleOnly);
+        assert_eq!(de::from_str_with::<Entry>(simple, &simple_only)?, expected);
+        assert!(
+            de::from_str_with::<Entry>(bulk, &simple_only)
+                .is_err_and(|err| matches!(err, Error::UnexpectedSign { found: '$', expected: '+', pos: 4 }))
+        );
+        let bulk_only = de::DeserializerBuilder::new().string_mode(StringMode::BulkOnly);
+        assert_eq!(de::from_str_with::<Entry>(bulk, &bulk_only)?, expected);
+        assert!(
+            de::from_str_with::<Entry>(simple, &bulk_only)
+                .is_err_and(|err| matches!(err, Error::UnexpectedSign { found: '+', expected: '$', pos: 4 }))
+        );
+        // the raw frame is kept as is
+        assert_eq!(de::from_str_with::<RESPType>("+foo\r\n", &bulk_only)?, RESPType::SimpleString("foo".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_insensitive_identifiers() -> Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        enum Command {
+            Get { key: String },
+            Ping,
+            Del(String),
+        }
+
+        let config = de::DeserializerBuilder::new().case_insensitive_identifiers(true);
+        let commands = "*3\r\n$4\r\nPING\r\n*3\r\n$3\r\nget\r\n$3\r\nKEY\r\n$1\r\na\r\n*2\r\n+dEl\r\n$1\r\nb\r\n";
+        assert_eq!(
+            de::from_str_with::<Vec<Command>>(commands, &config)?,
+            [Command::Ping, Command::Get { key: "a".to_owned() }, Command::Del("b".to_owned())]
+        );
+        assert!(de::from_str::<Command>("$4\r\nPING\r\n").is_err_and(|err| err.kind() == ErrorKind::Message));
+
+        #[derive(Debug, Deserialize)]
+        enum Clash {
+            Get,
+            #[serde(rename = "GET")]
+            Upper,
+            Set,
+        }
+        let err = de::from_str_with::<Clash>("+Get\r\n", &config).unwrap_err();
+        assert_eq!(err.to_string(), "`Get` and `GET` can't be told apart with case insensitive identifiers");
+        assert!(matches!(de::from_str::<Clash>("+GET\r\n")?, Clash::Upper));
+        // only names matching both are refused
+        assert!(matches!(de::from_str_with::<Clash>("+set\r\n", &config)?, Clash::Set));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() -> Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Lenient {
+            name: String,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            name: String,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Flattened {
+            #[serde(flatten)]
+            inner: Lenient,
+        }
+
+        let extra = "*4\r\n$4\r\nname\r\n$3\r\nfoo\r\n$3\r\nage\r\n:3\r\n";
+        assert_eq!(de::from_str::<Lenient>(extra)?, Lenient { name: "foo".to_owned() });
+        assert!(
+            de::from_str::<Strict>(extra)
+                .is_err_and(|err| matches!(err, Error::UnknownField { field, pos: 23 } if field == "age"))
+        );
+        let config = de::DeserializerBuilder::new().deny_unknown_fields(true);
+        assert!(
+            de::from_str_with::<Lenient>(extra, &config)
+                .is_err_and(|err| matches!(err, Error::UnknownField { field, pos: 23 } if field == "age"))
+        );
+        let resp3 = "%2\r\n+name\r\n+foo\r\n+age\r\n:3\r\n";
+        assert!(
+            de::from_str_with::<Lenient>(resp3, &config)
+                .is_err_and(|err| matches!(err, Error::UnknownField { field, pos: 17 } if field == "age"))
+        );
+        // flattened structs are read as maps, which keep their unknown keys
+        assert_eq!(
+            de::from_str_with::<Flattened>(extra, &config)?,
+            Flattened { inner: Lenient { name: "foo".to_owned() } }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_key() -> Result<()> {
+        #[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+        struct Key(#[serde(with = "serde_bytes_buf")] Vec<u8>);
+
+        let text = "*6\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n$1\r\na\r\n:3\r\n";
+        let binary = b"%2\r\n$2\r\n\xff\x00\r\n:1\r\n$2\r\n\xff\x00\r\n:2\r\n";
+        let key = |k: &[u8]| Key(k.to_vec());
+        let config = |policy| de::DeserializerBuilder::new().on_duplicate_key(policy);
+
+        let last: HashMap<Key, i64> = de::from_str_with(text, &config(DuplicateKeyPolicy::LastWins))?;
+        assert_eq!(last, HashMap::from([(key(b"a"), 3), (key(b"b"), 2)]));
+        let last: HashMap<Key, i64> = de::from_slice_with(binary, &config(DuplicateKeyPolicy::LastWins))?;
+        assert_eq!(last, HashMap::from([(key(b"\xff\x00"), 2)]));
+
+        let first: HashMap<Key, i64> = de::from_str_with(text, &config(DuplicateKeyPolicy::FirstWins))?;
+        assert_eq!(first, HashMap::from([(key(b"a"), 1), (key(b"b"), 2)]));
+        let first: HashMap<Key, i64> = de::from_slice_with(binary, &config(DuplicateKeyPolicy::FirstWins))?;
+        assert_eq!(first, HashMap::from([(key(b"\xff\x00"), 1)]));
+
+        assert!(
+            de::from_str_with::<HashMap<Key, i64>>(text, &config(DuplicateKeyPolicy::Error))
+                .is_err_and(|err| matches!(err, Error::DuplicateKey { key, pos: 26 } if key == "a"))
+        );
+        assert!(
+            de::from_slice_with::<HashMap<Key, i64>>(binary, &config(DuplicateKeyPolicy::Error))
+                .is_err_and(|err| matches!(err, Error::DuplicateKey { key, pos: 16 } if key == "\u{fffd}\0"))
+        );
+        // keys are compared by content
+        assert!(
+            de::from_str_with::<HashMap<String, i64>>("%2\r\n$1\r\na\r\n:1\r\n+a\r\n:2\r\n", &config(DuplicateKeyPolicy::Error))
+                .is_err_and(|err| err.kind() == ErrorKind::DuplicateKey)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_on_frame() -> Result<()> {
+        let frames = RefCell::new(Vec::new());
+        let pipeline = "*2\r\n$3\r\nGET\r\n*1\r\n:1\r\n+OK\r\n$-1\r\n%1\r\n+a\r\n:1\r\n-ERR\r\n:7\r\n";
+        let mut de = de::Deserializer::from_str(pipeline).on_frame(|kind, size| frames.borrow_mut().push((kind, size)));
+        RESPType::deserialize(&mut de)?;
+        String::deserialize(&mut de)?;
+        Option::<String>::deserialize(&mut de)?;
+        HashMap::<String, i64>::deserialize(&mut de)?;
+        assert!(i64::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::ErrorReply));
+        u8::deserialize(&mut de)?;
+        de.end()?;
+        drop(de);
+        assert_eq!(
+            frames.into_inner(),
+            [
+                (RespKind::Array, 21),
+                (RespKind::SimpleString, 5),
+                (RespKind::BulkString, 5),
+                (RespKind::Map, 12),
+                (RespKind::Error, 6),
+                (RespKind::Integer, 4),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_integer_keys() -> Result<()> {
+        let hgetall = "*4\r\n$2\r\n42\r\n$3\r\nfoo\r\n$2\r\n-7\r\n$3\r\nbar\r\n";
+        let map: HashMap<i64, String> = de::from_str(hgetall)?;
+        assert_eq!(map, HashMap::from([(42, "foo".to_owned()), (-7, "bar".to_owned())]));
+        // integer replies and simple strings work as keys too
+        let map: HashMap<u8, i64> = de::from_str("%2\r\n:1\r\n$2\r\n10\r\n+2\r\n:20\r\n")?;
+        assert_eq!(map, HashMap::from([(1, 10), (2, 20)]));
+        assert!(
+            de::from_str::<HashMap<i64, String>>("*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n")
+                .is_err_and(|err| matches!(err, Error::ParseIntError { pos: 4, .. }))
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_index_map() -> Result<()> {
+        let config = "*6\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n$10\r\nappendonly\r\n$2\r\nno\r\n$4\r\nsave\r\n$0\r\n\r\n";
+        let map: indexmap::IndexMap<String, String> = de::from_str(config)?;
+        let keys: Vec<&str> = map.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["maxmemory", "appendonly", "save"]);
+        assert_eq!(map["appendonly"], "no");
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Probe {
+        flag: bool,
+        small: u8,
+        letter: char,
+        unit: (),
+        pair: (i32, String),
+        kind: Kind,
+        #[serde(with = "serde_bytes_buf")]
+        payload: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Kind {
+        Empty,
+        Wrap(i64),
+        Pair(i64, i64),
+        Named { x: i64 },
+    }
+
+    mod serde_bytes_buf {
+        use serde::Deserializer;
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<u8>, D::Error> {
+            struct BufVisitor;
+            impl serde::de::Visitor<'_> for BufVisitor {
+                type Value = Vec<u8>;
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                    Ok(v.to_vec())
+                }
+            }
+            de.deserialize_byte_buf(BufVisitor)
+        }
+    }
+
+    #[test]
+    fn test_struct() -> Result<()> {
+        let probe = concat!(
+            "*14\r\n",
+            "$4\r\nflag\r\n:1\r\n",
+            "$5\r\nsmall\r\n:255\r\n",
+            "$6\r\nletter\r\n+x\r\n",
+            "$4\r\nunit\r\n$-1\r\n",
+            "$4\r\npair\r\n*2\r\n:-1\r\n$3\r\none\r\n",
+            "$4\r\nkind\r\n$5\r\nEmpty\r\n",
+            "$7\r\npayload\r\n$2\r\n\x00\x01\r\n",
+        );
+        assert_eq!(de::from_str::<Probe>(probe)?, Probe {
+            flag: true,
+            small: 255,
+            letter: 'x',
+            unit: (),
+            pair: (-1, "one".to_owned()),
+            kind: Kind::Empty,
+            payload: vec![0, 1],
+        });
+        assert!(de::from_str::<bool>(":2\r\n").is_err());
+        assert!(de::from_str::<u8>(":256\r\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enum() -> Result<()> {
+        assert_eq!(de::from_str::<Kind>("+Empty\r\n")?, Kind::Empty);
+        assert_eq!(de::from_str::<Kind>("*1\r\n+Empty\r\n")?, Kind::Empty);
+        assert_eq!(de::from_str::<Kind>("*2\r\n+Wrap\r\n:3\r\n")?, Kind::Wrap(3));
+        assert_eq!(de::from_str::<Kind>("*3\r\n+Pair\r\n:1\r\n:2\r\n")?, Kind::Pair(1, 2));
+        assert_eq!(de::from_str::<Kind>("*3\r\n+Named\r\n+x\r\n:1\r\n")?, Kind::Named { x: 1 });
+        for frame in ["*0\r\n", "$-1\r\n", "+Wrap\r\n", "*2\r\n+Pair\r\n:1\r\n", "*1\r\n+Other\r\n"] {
+            assert!(de::from_str::<Kind>(frame).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_double() -> Result<()> {
+        for (frame, v) in [(",2.71\r\n", 2.71), (",-2.5\r\n", -2.5), (",10\r\n", 10.0), (",1.5e3\r\n", 1500.0)] {
+            assert_eq!(de::from_str::<f64>(frame)?, v);
+            assert_eq!(de::from_str::<RESPType>(frame)?, RESPType::Double(v));
+        }
+        assert_eq!(de::from_str::<f64>(",inf\r\n")?, f64::INFINITY);
+        assert_eq!(de::from_str::<RESPType>(",-inf\r\n")?, RESPType::Double(f64::NEG_INFINITY));
+        assert!(de::from_str::<f64>(",nan\r\n")?.is_nan());
+        assert_eq!(de::from_str::<f32>(",0.5\r\n")?, 0.5);
+        assert_eq!(de::from_str::<f64>(":1\r\n")?, 1.0);
+        assert_eq!(de::from_str::<Option<f64>>("$4\r\n2.75\r\n")?, Some(2.75));
+        assert_eq!(de::from_str::<Option<f64>>("$-1\r\n")?, None);
+        assert!(de::from_str::<f64>(",1.2.3\r\n").is_err_and(|err| err.kind() == ErrorKind::Message));
+        assert!(de::from_str::<f64>("*0\r\n").is_err());
         Ok(())
     }
 
     #[test]
-    fn test_null() -> Result<()> {
-        let null_bulk_str = "$-1\r\n";
-        let null_array = "*-1\r\n";
-        let resp_null: RESPType = de::from_str(null_bulk_str)?;
-        assert_eq!(resp_null, RESPType::None);
-        let resp_null: RESPType = de::from_str(null_array)?;
-        assert_eq!(resp_null, RESPType::None);
+    fn test_boolean() -> Result<()> {
+        assert_eq!(de::from_str::<RESPType>("#t\r\n")?, RESPType::Boolean(true));
+        assert_eq!(de::from_str::<RESPType>("#f\r\n")?, RESPType::Boolean(false));
+        assert_eq!(
+            de::from_str::<RESPType>("*2\r\n#t\r\n:1\r\n")?,
+            RESPType::Array(vec![RESPType::Boolean(true), RESPType::Integer(1)])
+        );
+        assert!(
+            de::from_str::<RESPType>("#x\r\n")
+                .is_err_and(|err| matches!(err, Error::UnexpectedSign { expected: 't', found: 'x', pos: 1 }))
+        );
+        assert!(de::from_str::<bool>("#tt\r\n").is_err_and(|err| err.kind() == ErrorKind::TrailingCharacters));
         Ok(())
     }
 
@@ -587,16 +3031,679 @@ mod de_test {
         let int = ":11111111111111111111111\r\n";
         assert!(
             de::from_str::<RESPType>(int)
+                .is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow)
+        );
+        assert!(de::from_str::<i64>(":9223372036854775808\r\n").is_err_and(|err| matches!(err, Error::IntegerOverflow { pos: 1 })));
+        assert!(de::from_str::<i64>(":-9223372036854775809\r\n").is_err_and(|err| err.kind() == ErrorKind::IntegerOverflow));
+        Ok(())
+    }
+
+    #[test]
+    fn test_integer_scan() -> Result<()> {
+        assert_eq!(de::from_str::<i64>(":1234567\r\n")?, 1234567);
+        assert_eq!(de::from_str::<i64>(":-42\r\n")?, -42);
+        assert_eq!(de::from_str::<i64>(":-9223372036854775808\r\n")?, i64::MIN);
+        assert_eq!(de::from_str::<i64>(":9223372036854775807\r\n")?, i64::MAX);
+        assert!(de::from_str::<i64>(":12a\r\n").is_err_and(|err| matches!(err, Error::InvalidInteger { pos: 3 })));
+        assert!(de::from_str::<i64>(":-\r\n").is_err_and(|err| err.kind() == ErrorKind::InvalidInteger));
+        assert!(de::from_str::<i64>(":\r\n").is_err_and(|err| err.kind() == ErrorKind::InvalidInteger));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_array_too_large() -> Result<()> {
+        // no element follows the header: the limit must fire before reading any
+        let array = "*4\r\n";
+        let mut de = de::Deserializer::from_str(array).with_max_array_len(3);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| {
+                    if let Error::ArrayTooLarge { declared, limit } = err {
+                        return declared == 4 && limit == 3;
+                    }
+                    false
+                })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_max_depth() -> Result<()> {
+        let nested = |depth: usize| [b"*1\r\n".repeat(depth), b":1\r\n".to_vec()].concat();
+        assert!(de::from_slice::<RESPType>(&nested(128)).is_ok());
+        let deep = nested(100_000);
+        assert!(
+            de::from_slice::<RESPType>(&deep)
+                .is_err_and(|err| matches!(err, Error::DepthLimitExceeded { limit: 128 }))
+        );
+        assert!(
+            de::from_reader::<_, RESPType>(&mut deep.as_slice())
+                .is_err_and(|err| err.kind() == ErrorKind::DepthLimitExceeded)
+        );
+        let mut parser = crate::Parser::new();
+        parser.feed(&deep);
+        assert!(parser.next_value::<RESPType>().is_err_and(|err| err.kind() == ErrorKind::DepthLimitExceeded));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_too_many_elements() -> Result<()> {
+        // 1 + 3 arrays + 9 integers, no single array declares more than 3
+        let mut array = String::from("*3\r\n");
+        for _ in 0..3 {
+            array.push_str("*3\r\n:1\r\n:2\r\n:3\r\n");
+        }
+        let mut de = de::Deserializer::from_str(&array)
+            .with_max_array_len(3)
+            .with_max_elements(12);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| err.kind() == ErrorKind::TooManyElements)
+        );
+        let mut de = de::Deserializer::from_str(&array)
+            .with_max_array_len(3)
+            .with_max_elements(13);
+        assert!(matches!(RESPType::deserialize(&mut de)?, RESPType::Array(arr) if arr.len() == 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_too_many_elements_flat_and_empty() -> Result<()> {
+        let flat = format!("*10\r\n{}", ":1\r\n".repeat(10));
+        let mut de = de::Deserializer::from_str(&flat).with_max_elements(10);
+        assert!(Vec::<i64>::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::TooManyElements));
+        let mut de = de::Deserializer::from_str(&flat).with_max_elements(11);
+        assert_eq!(Vec::<i64>::deserialize(&mut de)?.len(), 10);
+        // tiny in bytes, yet every empty array counts
+        let nested = format!("{}*0\r\n", "*1\r\n".repeat(8));
+        let mut de = de::Deserializer::from_str(&nested).with_max_elements(8);
+        assert!(RESPType::deserialize(&mut de).is_err_and(|err| err.kind() == ErrorKind::TooManyElements));
+        Ok(())
+    }
+
+    #[test]
+    fn test_elements_count_per_top_level_value() -> Result<()> {
+        let pipeline = "*2\r\n:1\r\n:2\r\n*2\r\n:3\r\n:4\r\n";
+        let mut de = de::Deserializer::from_str(pipeline).with_max_elements(3);
+        RESPType::deserialize(&mut de)?;
+        RESPType::deserialize(&mut de)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_bulk_string_overflow() -> Result<()> {
+        let mut de = de::Deserializer::from_str("$4\r\nfour\r\n$5\r\nfives\r\n").with_max_bulk_len(4);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::BulkString(b"four".to_vec()));
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow)
+        );
+        assert!(
+            de::from_str::<RESPType>("$536870913\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_array_overrun() -> Result<()> {
+        let overrun = "*2\r\n:1\r\n*2\r\n:2\r\n:3\r\n:4\r\n:5\r\n";
+        let mut de = de::Deserializer::from_str(overrun).report_array_overrun(true);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| matches!(err, Error::ArrayOverrun { declared: 2, pos: 20 }))
+        );
+        assert!(de::from_str::<RESPType>(overrun).is_err_and(|err| err.kind() == ErrorKind::TrailingCharacters));
+        let mut de = de::Deserializer::from_str("*1\r\n:1\r\n").report_array_overrun(true);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::Array(vec![RESPType::Integer(1)]));
+        // the error of a failing element is not mistaken for an overrun
+        let config = de::DeserializerBuilder::default().report_array_overrun(true);
+        assert!(
+            de::from_slice_with::<Vec<i64>>(b"*2\r\n:1\r\n+x\r\n:3\r\n", &config)
+                .is_err_and(|err| err.kind() == ErrorKind::ParseIntError)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unconsumed_elements() -> Result<()> {
+        let array = "*3\r\n:1\r\n:2\r\n*2\r\n:3\r\n:4\r\n";
+        assert_eq!(de::from_str::<(i64, i64)>(array)?, (1, 2));
+        let nested = "*2\r\n*3\r\n:1\r\n:2\r\n*1\r\n:9\r\n*2\r\n:4\r\n:5\r\n";
+        assert_eq!(de::from_str::<Vec<(i64, i64)>>(nested)?, vec![(1, 2), (4, 5)]);
+        // the next frame is not mistaken for the leftovers
+        let mut de = de::Deserializer::from_str("*3\r\n:1\r\n:2\r\n:3\r\n:4\r\n");
+        assert_eq!(<(i64, i64)>::deserialize(&mut de)?, (1, 2));
+        assert_eq!(i64::deserialize(&mut de)?, 4);
+
+        let mut de = de::Deserializer::from_str(nested).strict_array_len(true);
+        assert!(
+            Vec::<(i64, i64)>::deserialize(&mut de)
+                .is_err_and(|err| matches!(err, Error::UnconsumedElements { remaining: 1, pos: 16 }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_end() -> Result<()> {
+        let mut de = de::Deserializer::from_str(":1\r\n+OK\r\n");
+        assert_eq!(i64::deserialize(&mut de)?, 1);
+        assert!(!de.is_finished());
+        assert!(de.end().is_err_and(|err| matches!(err, Error::TrailingCharacters(4))));
+        assert_eq!(String::deserialize(&mut de)?, "OK");
+        assert!(de.is_finished());
+        de.end()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder() -> Result<()> {
+        // defaults match the plain functions
+        let defaults = de::DeserializerBuilder::new();
+        for frame in ["*3\r\n:1\r\n:2\r\n:3\r\n", "$3\r\nfoo\r\n", "+OK\r\n:1\r\n", "*1\r\n$3\r\nfo\r\n"] {
+            assert_eq!(
+                format!("{:?}", de::from_str::<RESPType>(frame)),
+                format!("{:?}", de::from_str_with::<RESPType>(frame, &defaults)),
+            );
+        }
+
+        let config = de::DeserializerBuilder::new()
+            .with_max_array_len(2)
+            .strict_array_len(true)
+            .no_bulk_trailing_crlf(true);
+        assert!(
+            de::from_str_with::<Vec<i64>>("*3\r\n:1\r\n:2\r\n:3\r\n", &config)
+                .is_err_and(|err| err.kind() == ErrorKind::ArrayTooLarge)
+        );
+        assert!(
+            de::from_str_with::<(i64,)>("*2\r\n:1\r\n:2\r\n", &config)
+                .is_err_and(|err| err.kind() == ErrorKind::UnconsumedElements)
+        );
+        // the builder is reusable
+        assert_eq!(de::from_slice_with::<RESPType>(b"$3\r\nfoo", &config)?, RESPType::BulkString(b"foo".to_vec()));
+        let mut reader = BufReader::new(&b"$3\r\nbar"[..]);
+        assert_eq!(
+            de::from_reader_with::<_, RESPType>(&mut reader, &config.clone())?,
+            RESPType::BulkString(b"bar".to_vec())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resp3_map() -> Result<()> {
+        let map = "%3\r\n+a\r\n:1\r\n+b\r\n:2\r\n+a\r\n:3\r\n";
+        assert_eq!(
+            de::from_str::<Vec<(String, i64)>>(map)?,
+            vec![("a".to_owned(), 1), ("b".to_owned(), 2), ("a".to_owned(), 3)]
+        );
+        assert_eq!(
+            de::from_str::<HashMap<String, i64>>(map)?,
+            HashMap::from([("a".to_owned(), 3), ("b".to_owned(), 2)])
+        );
+        assert_eq!(de::from_str::<Vec<(String, i64)>>("%0\r\n")?, vec![]);
+        assert!(
+            de::from_str::<Vec<(String, i64)>>("%-1\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::ParseIntError)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resp3_null() -> Result<()> {
+        assert_eq!(de::from_str::<RESPType>("_\r\n")?, RESPType::Null);
+        assert_eq!(de::from_str::<Option<i64>>("_\r\n")?, None);
+        assert_eq!(de::from_str::<Vec<Option<i64>>>("*2\r\n_\r\n:1\r\n")?, vec![None, Some(1)]);
+        assert!(de::from_str::<RESPType>("_x\r\n").is_err_and(|err| matches!(err, Error::TrailingCharacters(1))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_number() -> Result<()> {
+        let digits = "3492890328409238509324850943850943825024385";
+        let frame = format!("({digits}\r\n");
+        assert_eq!(de::from_str::<RESPType>(&frame)?, RESPType::BigNumber(digits.to_owned()));
+        assert_eq!(de::from_str::<String>(&frame)?, digits);
+        assert_eq!(de::from_str::<RESPType>("*1\r\n(-1\r\n")?, RESPType::Array(vec![RESPType::BigNumber("-1".to_owned())]));
+        for invalid in ["(\r\n", "(-\r\n", "(12a\r\n"] {
+            assert!(de::from_str::<RESPType>(invalid).is_err_and(|err| err.kind() == ErrorKind::Message));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient_line_endings() -> Result<()> {
+        let crlf = "*3\r\n+OK\r\n:12\r\n$4\r\na\nb\n\r\n";
+        let lf = "*3\n+OK\n:12\n$4\na\nb\n\n";
+        let expected = RESPType::Array(vec![
+            RESPType::ok(),
+            RESPType::Integer(12),
+            RESPType::BulkString(b"a\nb\n".to_vec()),
+        ]);
+        let lenient = de::DeserializerBuilder::new().lenient_line_endings(true);
+        assert_eq!(de::from_str_with::<RESPType>(crlf, &lenient)?, expected);
+        assert_eq!(de::from_str_with::<RESPType>(lf, &lenient)?, expected);
+        assert_eq!(de::from_str::<RESPType>(crlf)?, expected);
+        assert!(de::from_str::<RESPType>(lf).is_err_and(|err| matches!(err, Error::UnexpectedLF(2))));
+
+        // offsets stay exact with the shorter terminator
+        let mut de = lenient.build(b"+OK\n:1\r\n+x\ry\n");
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::ok());
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::Integer(1));
+        assert!(RESPType::deserialize(&mut de).is_err_and(|err| matches!(err, Error::UnexpectedCR(10))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_ending() -> Result<()> {
+        let config = |line_ending| de::DeserializerBuilder::new().line_ending(line_ending);
+        for line_ending in [LineEnding::Lf, LineEnding::Auto] {
+            assert_eq!(de::from_str_with::<RESPType>("+OK\n", &config(line_ending))?, RESPType::ok());
+            assert_eq!(de::from_str_with::<RESPType>("$2\nab\n", &config(line_ending))?, bulk!("ab"));
+        }
+        for line_ending in [LineEnding::CrLf, LineEnding::Auto] {
+            assert_eq!(de::from_str_with::<RESPType>("+OK\r\n", &config(line_ending))?, RESPType::ok());
+            assert_eq!(de::from_str_with::<RESPType>("$2\r\nab\r\n", &config(line_ending))?, bulk!("ab"));
+        }
+        assert!(
+            de::from_str_with::<RESPType>("+OK\n", &config(LineEnding::CrLf))
+                .is_err_and(|err| matches!(err, Error::UnexpectedLF(3)))
+        );
+        assert!(
+            de::from_str_with::<RESPType>("+OK\r\n", &config(LineEnding::Lf))
+                .is_err_and(|err| matches!(err, Error::UnexpectedCR(3)))
+        );
+        assert!(
+            de::from_str_with::<RESPType>("$2\nab\r\n", &config(LineEnding::Lf))
+                .is_err_and(|err| err.kind() == ErrorKind::WrongSizeOfBulkString)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_type() -> Result<()> {
+        let frames: [(&[u8], RespKind); 15] = [
+            (b"+OK\r\n", RespKind::SimpleString),
+            (b"-ERR\r\n", RespKind::Error),
+            (b":1\r\n", RespKind::Integer),
+            (b"$-1\r\n", RespKind::BulkString),
+            (b"*0\r\n", RespKind::Array),
+            (b"_\r\n", RespKind::Null),
+            (b"#t\r\n", RespKind::Boolean),
+            (b",1.5\r\n", RespKind::Double),
+            (b"(1\r\n", RespKind::BigNumber),
+            (b"!3\r\nERR\r\n", RespKind::BulkError),
+            (b"=7\r\ntxt:abc\r\n", RespKind::VerbatimString),
+            (b"%0\r\n", RespKind::Map),
+            (b"~0\r\n", RespKind::Set),
+            (b"|0\r\n", RespKind::Attribute),
+            (b">0\r\n", RespKind::Push),
+        ];
+        for (frame, kind) in frames {
+            assert_eq!(de::peek_type(frame)?, kind);
+        }
+        // only the leading byte is looked at
+        assert_eq!(de::peek_type(b"*")?, RespKind::Array);
+        assert!(de::peek_type(b"").is_err_and(|err| err.kind() == ErrorKind::Eof));
+        assert!(de::peek_type(b"OK\r\n").is_err_and(|err| matches!(err, Error::ExpectedSign(0))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_deserializer() -> Result<()> {
+        let values = de::Deserializer::from_str("+OK\r\n:5\r\n$3\r\nfoo\r\n")
+            .into_iter::<RESPType>()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            values,
+            vec![RESPType::ok(), RESPType::Integer(5), RESPType::BulkString(b"foo".to_vec())]
+        );
+        assert_eq!(de::Deserializer::from_str("").into_iter::<RESPType>().count(), 0);
+
+        let mut stream = de::Deserializer::from_str(":1\r\n:x\r\n:3\r\n").into_iter::<i64>();
+        assert_eq!(stream.next().transpose()?, Some(1));
+        assert_eq!(stream.byte_offset(), 4);
+        assert!(stream.next().is_some_and(|item| item.is_err_and(|err| err.kind() == ErrorKind::InvalidInteger)));
+        assert_eq!(stream.byte_offset(), 4);
+        assert!(stream.next().is_none());
+
+        let mut stream = de::Deserializer::from_str("+a\r\n$3\r\nfo").into_iter::<String>();
+        assert_eq!(stream.next().transpose()?, Some("a".to_owned()));
+        assert!(stream.next().is_some_and(|item| item.is_err_and(|err| err.kind() == ErrorKind::Eof)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_len() -> Result<()> {
+        assert_eq!(de::array_len(b"*3\r\n$3\r\nDEL\r\n$1\r\na\r\n$1\r\nb\r\n")?, Some(3));
+        // the elements are not read
+        assert_eq!(de::array_len(b"*3\r\n$3\r\nDEL")?, Some(3));
+        assert_eq!(de::array_len(b"*0\r\n")?, Some(0));
+        assert_eq!(de::array_len(b"*-1\r\n")?, None);
+        assert!(
+            de::array_len(b"$3\r\nGET\r\n")
+                .is_err_and(|err| matches!(err, Error::UnexpectedSign { expected: '*', found: '$', pos: 0 }))
+        );
+        assert!(de::array_len(b"*3").is_err_and(|err| err.kind() == ErrorKind::Eof));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_reply() -> Result<()> {
+        let reply = |code: &str, message: &str| RespError { code: code.to_owned(), message: message.to_owned() };
+        assert!(
+            de::from_str::<i64>("-ERR oops\r\n")
+                .is_err_and(|err| matches!(err, Error::ErrorReply(e) if e == reply("ERR", "oops")))
+        );
+        let wrongtype = "*2\r\n$1\r\na\r\n-WRONGTYPE Operation against a key\r\n";
+        assert!(
+            de::from_str::<Vec<String>>(wrongtype)
+                .is_err_and(|err| matches!(err, Error::ErrorReply(e) if e == reply("WRONGTYPE", "Operation against a key")))
+        );
+        assert!(
+            de::from_str::<Option<String>>("-not a code\r\n")
+                .is_err_and(|err| matches!(err, Error::ErrorReply(e) if e == reply("", "not a code")))
+        );
+        assert_eq!(de::from_str::<RESPType>("-ERR oops\r\n")?, RESPType::Error("ERR oops".to_owned()));
+        // skipped elements may be error replies
+        assert_eq!(de::from_str::<(i64,)>("*2\r\n:1\r\n-ERR\r\n")?, (1,));
+
+        let config = de::DeserializerBuilder::new().surface_error_replies(false);
+        assert_eq!(de::from_str_with::<String>("-ERR oops\r\n", &config)?, "ERR oops");
+        assert!(
+            de::from_str_with::<i64>("-ERR oops\r\n", &config)
+                .is_err_and(|err| err.kind() == ErrorKind::UnexpectedSign)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resp_error_kind() -> Result<()> {
+        let moved: RespError = de::from_str("-MOVED 3999 127.0.0.1:6381\r\n")?;
+        assert_eq!(moved.kind(), RespErrorKind::Moved { slot: 3999, addr: "127.0.0.1:6381".to_owned() });
+        let ask: RespError = de::from_str("-ASK 3999 127.0.0.1:6381\r\n")?;
+        assert_eq!(ask.kind(), RespErrorKind::Ask { slot: 3999, addr: "127.0.0.1:6381".to_owned() });
+        let wrongtype: RespError = de::from_str("-WRONGTYPE Operation against a key\r\n")?;
+        assert_eq!(wrongtype.kind(), RespErrorKind::WrongType);
+        assert_eq!(RespError::parse("ERR no such key").kind(), RespErrorKind::NoSuchKey);
+        assert_eq!(RespError::parse("MOVED somewhere").kind(), RespErrorKind::Other);
+        // replies in an array are read in place
+        let replies: Vec<RespError> = de::from_str("*2\r\n-ERR a\r\n-ERR b\r\n")?;
+        assert_eq!(replies, [RespError::parse("ERR a"), RespError::parse("ERR b")]);
+        assert!(
+            de::from_str::<RespError>(":1\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::Message)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_bulk_length() -> Result<()> {
+        let absurd = format!("*1\r\n${}\r\n", "9".repeat(10_000));
+        assert!(
+            de::from_str::<RESPType>(&absurd)
+                .is_err_and(|err| matches!(err, Error::InvalidBulkLength(5)))
+        );
+        assert!(
+            de::from_str::<RESPType>("$-000000000000000000001\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::InvalidBulkLength)
+        );
+        // 20 digits are scanned, then rejected as an overflow
+        assert!(
+            de::from_str::<RESPType>("$99999999999999999999\r\n")
                 .is_err_and(|err| err.kind() == ErrorKind::ParseIntError)
         );
         Ok(())
     }
 
+    #[test]
+    fn test_no_bulk_trailing_crlf() -> Result<()> {
+        let rdb = b"$9\r\nREDIS0011+FULLRESYNC\r\n";
+        let mut de = de::Deserializer::from_bytes(rdb).no_bulk_trailing_crlf(true);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::BulkString(b"REDIS0011".to_vec()));
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::SimpleString("FULLRESYNC".to_owned()));
+        assert!(
+            de::Deserializer::from_bytes(b"$9\r\nREDIS").no_bulk_trailing_crlf(true).parse_bytes()
+                .is_err_and(|err| err.kind() == ErrorKind::Eof)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_frame_too_large() -> Result<()> {
+        let array = "*3\r\n$5\r\nhello\r\n$5\r\nworld\r\n:1\r\n";
+        let mut de = de::Deserializer::from_str(array).with_max_frame_size(16);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| err.kind() == ErrorKind::FrameTooLarge)
+        );
+        // the limit applies to each top-level value on its own
+        let pipeline = "$5\r\nhello\r\n$5\r\nworld\r\n";
+        let mut de = de::Deserializer::from_str(pipeline).with_max_frame_size(11);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::BulkString(b"hello".to_vec()));
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::BulkString(b"world".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover() -> Result<()> {
+        let input = "*2\r\n:1\r\n?bad\r\n+OK\r\n";
+        let mut de = de::Deserializer::from_str(input);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| matches!(err, Error::ExpectedSign(8)))
+        );
+        assert_eq!(de.recover()?, 6);
+        assert_eq!(RESPType::deserialize(&mut de)?, RESPType::SimpleString("OK".to_owned()));
+
+        let mut de = de::Deserializer::from_str("?bad\r\n");
+        assert!(RESPType::deserialize(&mut de).is_err());
+        assert!(de.recover().is_err_and(|err| err.kind() == ErrorKind::Eof));
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_array() -> Result<()> {
+        let mut array = String::from("*100000\r\n");
+        for i in 0..100000 {
+            array.push_str(&format!(":{i}\r\n"));
+        }
+        let resp_arr: RESPType = de::from_str(&array)?;
+        assert!(matches!(resp_arr, RESPType::Array(arr) if arr.len() == 100000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_with_input() -> Result<()> {
+        let input = b"*2\r\n$3\r\n\x00ab\r\n:1\r\n+extra\r\n";
+        let err = de::from_slice::<RESPType>(input).unwrap_err();
+        assert_eq!(err.offset(), Some(17));
+        assert_eq!(err.with_input(input).to_string(), format!("{err}, at `+extra\\r\\n`"));
+        // an offset past the input given renders nothing of it
+        assert_eq!(err.with_input(&input[..4]).to_string(), format!("{err}, at ``"));
+        let err = de::from_slice::<RESPType>(b"$3\r\n\xfe").unwrap_err();
+        assert_eq!(err.offset(), None);
+        assert_eq!(err.with_input(b"$3\r\n\xfe").to_string(), format!("{err}, at `$3\\r\\n\\xfe`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_offsets() -> Result<()> {
+        let fixture = "*3\r\n:32\r\n*2\r\n+foo\r\n$3\r\nbar\r\n*1\r\n:7\r\n";
+        assert!(de::from_str::<RESPType>(fixture).is_ok());
+        // (position to corrupt, replacement, expected kind)
+        let cases = [
+            (0, '?', ErrorKind::ExpectedSign),
+            (1, 'x', ErrorKind::ParseIntError),
+            (4, '?', ErrorKind::ExpectedSign),
+            (5, 'x', ErrorKind::InvalidInteger),
+            (9, '?', ErrorKind::ExpectedSign),
+            (10, 'x', ErrorKind::ParseIntError),
+            (13, '?', ErrorKind::ExpectedSign),
+            (15, '\r', ErrorKind::UnexpectedCR),
+            (19, '?', ErrorKind::ExpectedSign),
+            (20, 'x', ErrorKind::ParseIntError),
+            (26, 'x', ErrorKind::WrongSizeOfBulkString),
+            (28, '?', ErrorKind::ExpectedSign),
+            (32, '?', ErrorKind::ExpectedSign),
+            (33, 'x', ErrorKind::InvalidInteger),
+        ];
+        for (corrupt_at, ch, kind) in cases {
+            let mut input = fixture.to_owned();
+            input.replace_range(corrupt_at..corrupt_at + 1, &ch.to_string());
+            let err = de::from_str::<RESPType>(&input).unwrap_err();
+            let pos = match err {
+                Error::ExpectedSign(pos)
+                | Error::UnexpectedCR(pos)
+                | Error::ParseIntError { pos, .. }
+                | Error::InvalidInteger { pos }
+                | Error::WrongSizeOfBulkString { pos, .. } => pos,
+                _ => panic!("unexpected error {err} when corrupting byte {corrupt_at}"),
+            };
+            assert!(err.kind() == kind, "wrong error {err} when corrupting byte {corrupt_at}");
+            assert_eq!(pos, corrupt_at);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_trailing_characters() -> Result<()> {
+        let int = ":1\r\n:2\r\n";
+        assert!(
+            de::from_str::<RESPType>(int)
+                .is_err_and(|err| matches!(err, Error::TrailingCharacters(4)))
+        );
+        Ok(())
+    }
+
     #[test]
     fn from_reader() -> Result<()> {
         let mut buf = b"+hello\r\n".as_slice();
         let resp_str: RESPType = de::from_reader(&mut buf)?;
         assert_eq!(resp_str, RESPType::SimpleString("hello".to_owned()));
+        let frames = b"*2\r\n$5\r\nhel\nl\r\n:1\r\n$3\r\nfoo\r\n+rest\r\n";
+        let mut cursor = Cursor::new(&frames[..]);
+        let (bulk, n): (String, i64) = de::from_reader(&mut cursor)?;
+        assert_eq!((bulk.as_str(), n), ("hel\nl", 1));
+        let bulk: String = de::from_reader(&mut cursor)?;
+        assert_eq!(bulk, "foo");
+        assert_eq!(cursor.position(), frames.len() as u64 - 7);
+        assert!(de::from_reader::<_, RESPType>(&mut &b"*2\r\n:1\r\n"[..]).is_err_and(|err| err.kind() == ErrorKind::Eof));
+        assert!(de::from_reader::<_, RESPType>(&mut &b"$5\r\nab"[..]).is_err_and(|err| err.kind() == ErrorKind::Eof));
+        let permissive = de::DeserializerBuilder::new().with_limits(Limits::permissive());
+        let mut cursor = Cursor::new(&b"$3\r\nfoo\r\n"[..]);
+        assert_eq!(de::from_reader_with::<_, RESPType>(&mut cursor, &permissive)?, RESPType::BulkString(b"foo".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_limits() -> Result<()> {
+        use std::io::Read;
+
+        let strict = de::DeserializerBuilder::new().with_limits(Limits::strict());
+        let nested = b"*1\r\n".repeat(200_000);
+        assert!(
+            de::from_reader_with::<_, RESPType>(&mut nested.as_slice(), &strict)
+                .is_err_and(|err| matches!(err, Error::DepthLimitExceeded { limit: 32 }))
+        );
+
+        let mut limits = Limits::default();
+        limits.set_max_frame_size(100).set_max_elements(10);
+        let config = de::DeserializerBuilder::new().with_limits(limits);
+        let many = [&b"*1000\r\n"[..], &b":1\r\n".repeat(1000)].concat();
+        assert!(
+            de::from_reader_with::<_, RESPType>(&mut many.as_slice(), &config)
+                .is_err_and(|err| err.kind() == ErrorKind::TooManyElements)
+        );
+        let large = format!("*1\r\n${}\r\n{}\r\n", 200, "x".repeat(200));
+        let mut reader = Cursor::new(large.as_bytes());
+        assert!(
+            de::from_reader_with::<_, RESPType>(&mut reader, &config)
+                .is_err_and(|err| err.kind() == ErrorKind::FrameTooLarge)
+        );
+        assert!(reader.position() < 100);
+        // a line that never ends is not buffered past the limit
+        let mut endless = b"+".chain(std::io::repeat(b'a'));
+        assert!(
+            de::from_reader_with::<_, RESPType>(&mut endless, &config)
+                .is_err_and(|err| err.kind() == ErrorKind::FrameTooLarge)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_str_with_raw() -> Result<()> {
+        let input = "*2\r\n+foo\r\n:3\r\n$3\r\nbar\r\n";
+        let (value, raw) = de::from_str_with_raw(input)?;
+        assert_eq!(raw, "*2\r\n+foo\r\n:3\r\n");
+        assert_eq!(de::from_str::<RESPType>(raw)?, value);
+        Ok(())
+    }
+
+    #[test]
+    fn from_bufread() -> Result<()> {
+        let frames = b"*2\r\n$5\r\nhello\r\n:1\r\n+world\r\n".as_slice();
+        let mut reader = BufReader::new(frames);
+        let resp_arr: RESPType = de::from_bufread(&mut reader)?;
+        assert_eq!(resp_arr, RESPType::Array(vec![
+            RESPType::BulkString(b"hello".to_vec()),
+            RESPType::Integer(1),
+        ]));
+        assert_eq!(reader.buffer(), b"+world\r\n");
+        let resp_str: RESPType = de::from_bufread(&mut reader)?;
+        assert_eq!(resp_str, RESPType::SimpleString("world".to_owned()));
+        assert!(
+            de::from_bufread::<_, RESPType>(&mut reader)
+                .is_err_and(|err| err.kind() == ErrorKind::Eof)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_pipeline_bounded() -> Result<()> {
+        let frames = b":1\r\n:2\r\n*1\r\n:3\r\n:4\r\n:5\r\n".as_slice();
+        let mut reader = BufReader::new(frames);
+        let (values, more) = de::from_reader_pipeline_bounded::<_, RESPType>(&mut reader, 3);
+        assert_eq!(values, [RESPType::Integer(1), RESPType::Integer(2), RESPType::Array(vec![RESPType::Integer(3)])]);
+        assert!(more?);
+        assert_eq!(reader.buffer(), b":4\r\n:5\r\n");
+        let (values, more) = de::from_reader_pipeline_bounded::<_, i64>(&mut reader, 3);
+        assert_eq!((values, more?), (vec![4, 5], false));
+        let (values, more) = de::from_reader_pipeline_bounded::<_, i64>(&mut BufReader::new(b":1\r\n".as_slice()), 1);
+        assert_eq!((values, more?), (vec![1], false));
+
+        // the frames decoded before a failing one are returned with its error
+        let mut reader = BufReader::new(b":1\r\n#t\r\n:3\r\n".as_slice());
+        let (values, more) = de::from_reader_pipeline_bounded::<_, i64>(&mut reader, 3);
+        assert_eq!(values, [1]);
+        assert!(more.is_err_and(|err| err.kind() == ErrorKind::UnexpectedSign));
+        assert_eq!(reader.buffer(), b":3\r\n");
+        Ok(())
+    }
+
+    // A non-blocking socket: yields its bytes, then fails with `WouldBlock`.
+    struct WouldBlockReader<'a>(&'a [u8]);
+
+    impl Read for WouldBlockReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = self.0.read(buf)?;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn from_reader_pipeline_bounded_would_block() -> Result<()> {
+        for max in [2, 3] {
+            let mut reader = BufReader::new(WouldBlockReader(b":1\r\n:2\r\n"));
+            let (values, more) = de::from_reader_pipeline_bounded::<_, i64>(&mut reader, max);
+            assert_eq!((values, more?), (vec![1, 2], false));
+        }
+        let mut reader = BufReader::new(WouldBlockReader(b""));
+        let (values, more) = de::from_reader_pipeline_bounded::<_, i64>(&mut reader, 2);
+        assert!(values.is_empty());
+        assert!(more.is_err_and(|err| err.kind() == ErrorKind::IoError));
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use crate::{Error, RESPType, RespError, Result};
+
+/// Conversion out of an already parsed [`RESPType`], a lighter alternative
+/// to `Deserialize` for hand written types.
+///
+/// An error reply met where a value is expected is returned as `Error::ErrorReply`.
+pub trait RespDecode: Sized {
+    fn decode(resp: &RESPType) -> Result<Self>;
+}
+
+// An error for {resp} not being of the {expected} type.
+fn unexpected(expected: &'static str, resp: &RESPType) -> Error {
+    match resp {
+        RESPType::Error(reply) => Error::ErrorReply(RespError::parse(reply)),
+        other => Error::UnexpectedType { expected, found: other.type_name() },
+    }
+}
+
+impl RespDecode for RESPType {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        Ok(resp.clone())
+    }
+}
+
+/// Integers, or strings holding one.
+impl RespDecode for i64 {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        let text = match resp {
+            RESPType::Integer(n) => return Ok(*n),
+            RESPType::SimpleString(s) => s.as_bytes(),
+            RESPType::BulkString(b) => b.as_slice(),
+            other => return Err(unexpected("Integer", other)),
+        };
+        std::str::from_utf8(text)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(Error::UnexpectedType { expected: "Integer", found: resp.type_name() })
+    }
+}
+
+/// Simple strings and UTF-8 bulk strings.
+impl RespDecode for String {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        match resp {
+            RESPType::SimpleString(s) => Ok(s.clone()),
+            RESPType::BulkString(b) => Ok(String::from_utf8(b.clone())?),
+            other => Err(unexpected("String", other)),
+        }
+    }
+}
+
+/// Bulk strings and simple strings.
+impl RespDecode for Vec<u8> {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        match resp {
+            RESPType::BulkString(b) => Ok(b.clone()),
+            RESPType::SimpleString(s) => Ok(s.as_bytes().to_vec()),
+            other => Err(unexpected("BulkString", other)),
+        }
+    }
+}
+
+impl<T: RespDecode> RespDecode for Vec<T> {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        match resp {
+            RESPType::Array(arr) => arr.iter().map(T::decode).collect(),
+            other => Err(unexpected("Array", other)),
+        }
+    }
+}
+
+/// Any kind of null is `None`.
+impl<T: RespDecode> RespDecode for Option<T> {
+    fn decode(resp: &RESPType) -> Result<Self> {
+        match resp {
+            RESPType::None | RESPType::NullArray | RESPType::Null => Ok(None),
+            other => T::decode(other).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod decode_test {
+    use crate::error::ErrorKind;
+    use crate::{array, bulk, i64, none, simple};
+    use crate::{Error, RESPType, RespDecode, Result};
+
+    #[test]
+    fn test_decode() -> Result<()> {
+        let arr: RESPType = crate::from_str("*3\r\n:1\r\n:2\r\n:3\r\n")?;
+        assert_eq!(Vec::<i64>::decode(&arr)?, [1, 2, 3]);
+        assert_eq!(arr.decode::<Vec<i64>>()?, [1, 2, 3]);
+
+        let mixed = array!(bulk!("foo"), none!(), simple!("bar".to_owned()));
+        assert_eq!(
+            mixed.decode::<Vec<Option<String>>>()?,
+            [Some("foo".to_owned()), None, Some("bar".to_owned())]
+        );
+        assert_eq!(bulk!("42").decode::<i64>()?, 42);
+        assert_eq!(bulk!("\r\n").decode::<Vec<u8>>()?, b"\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_error() -> Result<()> {
+        assert!(
+            array!(i64!(1), bulk!("x")).decode::<Vec<i64>>()
+                .is_err_and(|err| matches!(err, Error::UnexpectedType { expected: "Integer", found: "BulkString" }))
+        );
+        assert!(
+            RESPType::Error("WRONGTYPE oops".to_owned()).decode::<String>()
+                .is_err_and(|err| matches!(err, Error::ErrorReply(e) if e.code == "WRONGTYPE"))
+        );
+        assert!(
+            RESPType::BulkString(vec![0xff]).decode::<String>()
+                .is_err_and(|err| err.kind() == ErrorKind::FromUtf8Error)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::RESPType;
+
+/// Conversion into a [`RESPType`], a lighter alternative to `Serialize`
+/// for building replies by hand.
+///
+/// Strings and byte slices become bulk strings, sequences and tuples arrays.
+pub trait RespEncode {
+    fn encode(&self) -> RESPType;
+}
+
+impl RespEncode for RESPType {
+    fn encode(&self) -> RESPType {
+        self.clone()
+    }
+}
+
+impl<T: RespEncode + ?Sized> RespEncode for &T {
+    fn encode(&self) -> RESPType {
+        (**self).encode()
+    }
+}
+
+impl RespEncode for str {
+    fn encode(&self) -> RESPType {
+        RESPType::BulkString(self.as_bytes().to_vec())
+    }
+}
+
+impl RespEncode for String {
+    fn encode(&self) -> RESPType {
+        self.as_str().encode()
+    }
+}
+
+impl RespEncode for [u8] {
+    fn encode(&self) -> RESPType {
+        RESPType::BulkString(self.to_vec())
+    }
+}
+
+impl RespEncode for Vec<u8> {
+    fn encode(&self) -> RESPType {
+        self.as_slice().encode()
+    }
+}
+
+impl RespEncode for i64 {
+    fn encode(&self) -> RESPType {
+        RESPType::Integer(*self)
+    }
+}
+
+impl RespEncode for i32 {
+    fn encode(&self) -> RESPType {
+        RESPType::Integer(i64::from(*self))
+    }
+}
+
+impl RespEncode for u32 {
+    fn encode(&self) -> RESPType {
+        RESPType::Integer(i64::from(*self))
+    }
+}
+
+impl<T: RespEncode> RespEncode for [T] {
+    fn encode(&self) -> RESPType {
+        RESPType::Array(self.iter().map(T::encode).collect())
+    }
+}
+
+impl<T: RespEncode> RespEncode for Vec<T> {
+    fn encode(&self) -> RESPType {
+        self.as_slice().encode()
+    }
+}
+
+/// `None` is a null bulk string.
+impl<T: RespEncode> RespEncode for Option<T> {
+    fn encode(&self) -> RESPType {
+        match self {
+            Some(value) => value.encode(),
+            None => RESPType::None,
+        }
+    }
+}
+
+macro_rules! tuple_encode {
+    ($($name: ident)+) => {
+        impl<$($name: RespEncode),+> RespEncode for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self) -> RESPType {
+                let ($($name,)+) = self;
+                RESPType::Array(vec![$($name.encode()),+])
+            }
+        }
+    };
+}
+
+tuple_encode!(A);
+tuple_encode!(A B);
+tuple_encode!(A B C);
+tuple_encode!(A B C D);
+
+#[cfg(test)]
+mod encode_test {
+    use crate::{array, bulk, i64, none};
+    use crate::{to_string, RESPType, RespEncode, Result};
+
+    #[test]
+    fn test_encode() -> Result<()> {
+        let reply = ("counter", 42i64).encode();
+        assert_eq!(reply, array!(bulk!("counter"), i64!(42)));
+        assert_eq!(to_string(&reply)?, "*2\r\n$7\r\ncounter\r\n:42\r\n");
+
+        let values = vec![Some("a".to_owned()), None];
+        assert_eq!(values.encode(), array!(bulk!("a"), none!()));
+        assert_eq!(b"\r\n"[..].encode(), RESPType::BulkString(b"\r\n".to_vec()));
+        assert_eq!((RESPType::ok(), vec![1u32, 2]).encode(), array!(RESPType::ok(), array!(i64!(1), i64!(2))));
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::helpers::debug_frame;
 use core::num;
 use serde::{de, ser};
 use std::fmt::{Display, Formatter};
@@ -14,15 +15,50 @@ pub enum Error {
     Message(String),
     Eof,
     Syntax(usize),
-    TrailingCharacters,
+    TrailingCharacters(usize),
     ExpectedSign(usize),
     UnexpectedCR(usize),
+    UnexpectedLF(usize),
     UnexpectedSign{ expected: char, found: char, pos: usize },
     BulkStringOverflow,
-    WrongSizeOfBulkString{ expected: usize, found: usize },
+    InvalidSimpleString,
+    UnexpectedType{ expected: &'static str, found: &'static str },
+    ArrayTooLarge{ declared: usize, limit: usize },
+    ArrayOverrun{ declared: usize, pos: usize },
+    UnconsumedElements{ remaining: usize, pos: usize },
+    /// An array read as a tuple struct has another number of elements than its fields.
+    WrongTupleLength{ expected: usize, found: usize, pos: usize },
+    FrameTooLarge{ limit: usize },
+    TooManyElements{ limit: usize },
+    /// Arrays or maps are nested deeper than the limit.
+    DepthLimitExceeded{ limit: usize },
+    /// Serializing would write more bytes than the limit.
+    OutputTooLarge{ limit: usize },
+    WrongSizeOfBulkString{ expected: usize, found: usize, pos: usize },
+    /// The length of a bulk string has more digits than any valid length.
+    InvalidBulkLength(usize),
+    /// An integer reply holding something else than digits, or not in canonical
+    /// form, e.g. `:007`, in strict integer mode.
+    InvalidInteger{ pos: usize },
+    /// An integer reply out of the range of `i64`.
+    IntegerOverflow{ pos: usize },
+    /// A command or subcommand name holds characters no command name has.
+    InvalidCommandName(String),
+    /// A struct was read from a map holding a key that isn't one of its fields.
+    UnknownField{ field: String, pos: usize },
+    /// A map holds the same key twice, `key` is lossily decoded as UTF-8.
+    DuplicateKey{ key: String, pos: usize },
+    /// Error reply sent by the server, e.g. `-ERR unknown command`.
+    RedisError(String),
+    /// Error reply met while deserializing into a type other than `RESPType`.
+    ErrorReply(RespError),
+    /// The value or operation has no RESP representation.
+    Unsupported{ what: &'static str },
     FromUtf8Error(string::FromUtf8Error),
-    IoError(io::Error),
-    ParseIntError(num::ParseIntError),
+    /// `written` is the number of bytes the serializer had handed to the writer
+    /// before it failed, it is always 0 for errors raised while reading.
+    IoError{ err: io::Error, written: usize },
+    ParseIntError{ err: num::ParseIntError, pos: usize },
 }
 
 impl Display for Error {
@@ -33,20 +69,58 @@ impl Display for Error {
             Error::Syntax(pos) => {
                 write!(f, "expect one of these signs: + - : $ * in {}th bytes", pos)
             }
-            Error::TrailingCharacters => write!(f, "trailing characters"),
+            Error::TrailingCharacters(pos) => write!(f, "trailing characters in {}th bytes", pos),
             Error::ExpectedSign(pos) => write!(f, "expect sign in {}th bytes", pos),
             Error::UnexpectedCR(pos) => write!(f, "meet unexpected '\r' in {}th bytes", pos),
+            Error::UnexpectedLF(pos) => write!(f, "meet unexpected '\n' in {}th bytes", pos),
             Error::UnexpectedSign { expected, found, pos } =>
                 write!(f, "found sign {} in pos {}, expected: {}", found, pos, expected),
             Error::BulkStringOverflow => write!(f, "bulk string overflow"),
-            Error::WrongSizeOfBulkString{ expected, found } => write!(
+            Error::InvalidSimpleString => write!(f, "simple string or error contains '\r' or '\n'"),
+            Error::UnexpectedType { expected, found } =>
+                write!(f, "unexpected type {}, expected: {}", found, expected),
+            Error::ArrayTooLarge { declared, limit } => write!(
+                f,
+                "array too large: declared {} elements, limit is {}",
+                declared, limit
+            ),
+            Error::ArrayOverrun { declared, pos } => write!(
+                f,
+                "array declared {} elements but another value follows in {}th bytes",
+                declared, pos
+            ),
+            Error::UnconsumedElements { remaining, pos } => write!(
+                f,
+                "array not fully consumed, {} elements left in {}th bytes",
+                remaining, pos
+            ),
+            Error::WrongTupleLength { expected, found, pos } => write!(
                 f,
-                "wrong size of bulk string: expected {} bytes, found {} bytes",
-                expected, found
+                "expected an array of {} elements, found {} elements in {}th bytes",
+                expected, found, pos
             ),
+            Error::FrameTooLarge { limit } => write!(f, "frame exceeds the limit of {} bytes", limit),
+            Error::TooManyElements { limit } => write!(f, "frame exceeds the limit of {} elements", limit),
+            Error::DepthLimitExceeded { limit } => write!(f, "arrays nested deeper than the limit of {}", limit),
+            Error::OutputTooLarge { limit } => write!(f, "output exceeds the limit of {} bytes", limit),
+            Error::WrongSizeOfBulkString{ expected, found, pos } => write!(
+                f,
+                "wrong size of bulk string: expected {} bytes, found {} bytes, missing \\r\\n in {}th bytes",
+                expected, found, pos
+            ),
+            Error::InvalidBulkLength(pos) => write!(f, "invalid bulk string length in {}th bytes", pos),
+            Error::InvalidInteger { pos } => write!(f, "invalid integer in {}th bytes", pos),
+            Error::IntegerOverflow { pos } => write!(f, "integer out of range in {}th bytes", pos),
+            Error::InvalidCommandName(name) => write!(f, "invalid command name {:?}", name),
+            Error::UnknownField { field, pos } => write!(f, "unknown field `{}` in {}th bytes", field, pos),
+            Error::DuplicateKey { key, pos } => write!(f, "duplicate key {:?} in {}th bytes", key, pos),
+            Error::RedisError(msg) => write!(f, "{}", msg),
+            Error::ErrorReply(err) => write!(f, "error reply: {}", err),
+            Error::Unsupported { what } => write!(f, "unsupported: {}", what),
             Error::FromUtf8Error(err) => write!(f, "{err}"),
-            Error::IoError(err) => write!(f, "{err}"),
-            Error::ParseIntError(err) => write!(f, "{err}"),
+            Error::IoError { err, written: 0 } => write!(f, "{err}"),
+            Error::IoError { err, written } => write!(f, "{err} after writing {written} bytes"),
+            Error::ParseIntError { err, pos } => write!(f, "{err} in {pos}th bytes"),
         }
     }
 }
@@ -69,6 +143,11 @@ impl de::Error for Error {
     {
         Error::Message(msg.to_string())
     }
+
+    // The position is filled in by the deserializer, which knows where the key began.
+    fn unknown_field(field: &str, _: &'static [&'static str]) -> Self {
+        Error::UnknownField { field: field.to_owned(), pos: 0 }
+    }
 }
 
 impl From<string::FromUtf8Error> for Error {
@@ -79,13 +158,134 @@ impl From<string::FromUtf8Error> for Error {
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        Error::IoError(err)
+        Error::IoError { err, written: 0 }
     }
 }
 
-impl From<num::ParseIntError> for Error {
-    fn from(err: num::ParseIntError) -> Self {
-        Error::ParseIntError(err)
+/// Error converting a [`RESPType`](crate::RESPType) into another type with `TryFrom`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConvertError {
+    /// Index of the element that failed to convert, when converting an array.
+    pub index: Option<usize>,
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl Display for ConvertError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)?;
+        if let Some(index) = self.index {
+            write!(f, " at index {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+/// An error reply split into its code, the leading upper case word
+/// such as `ERR` or `WRONGTYPE`, and the message following it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RespError {
+    pub code: String,
+    pub message: String,
+}
+
+impl RespError {
+    /// Split the text of an error reply. The code is empty if the first word
+    /// isn't upper case, the whole text is the message then.
+    pub fn parse(reply: &str) -> RespError {
+        let (code, message) = reply.split_once(' ').unwrap_or((reply, ""));
+        let is_code = !code.is_empty()
+            && code.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_');
+        if is_code {
+            RespError { code: code.to_owned(), message: message.to_owned() }
+        } else {
+            RespError { code: String::new(), message: reply.to_owned() }
+        }
+    }
+
+    /// Classify the error by its code.
+    ///
+    /// A `MOVED` or `ASK` redirection whose slot and address can't be parsed is `Other`.
+    pub fn kind(&self) -> RespErrorKind {
+        match self.code.as_str() {
+            "WRONGTYPE" => RespErrorKind::WrongType,
+            "MOVED" | "ASK" => {
+                let Some((slot, addr)) = self.redirection() else {
+                    return RespErrorKind::Other;
+                };
+                if self.code == "MOVED" {
+                    RespErrorKind::Moved { slot, addr }
+                } else {
+                    RespErrorKind::Ask { slot, addr }
+                }
+            }
+            "ERR" if self.message == "no such key" => RespErrorKind::NoSuchKey,
+            _ => RespErrorKind::Other,
+        }
+    }
+
+    /// The redirection this error asks for, `None` if it isn't a well formed
+    /// `MOVED` or `ASK` error.
+    pub fn redirect(&self) -> Option<Redirect> {
+        match self.kind() {
+            RespErrorKind::Moved { slot, addr } => Some(Redirect { kind: RedirectKind::Moved, slot, addr }),
+            RespErrorKind::Ask { slot, addr } => Some(Redirect { kind: RedirectKind::Ask, slot, addr }),
+            _ => None,
+        }
+    }
+
+    // Split the message of a redirection, `<slot> <host>:<port>`.
+    fn redirection(&self) -> Option<(u16, String)> {
+        let (slot, addr) = self.message.split_once(' ')?;
+        if addr.is_empty() || addr.contains(' ') {
+            return None;
+        }
+        Some((slot.parse().ok()?, addr.to_owned()))
+    }
+}
+
+/// Class of an error reply, see [`RespError::kind`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RespErrorKind {
+    /// `WRONGTYPE`, the key holds a value of another type.
+    WrongType,
+    /// `ERR no such key`
+    NoSuchKey,
+    /// `MOVED`, the slot is now served by the node at `addr`.
+    Moved { slot: u16, addr: String },
+    /// `ASK`, the slot is being migrated to the node at `addr`.
+    Ask { slot: u16, addr: String },
+    /// Any other error.
+    Other,
+}
+
+/// A cluster redirection, telling a client to retry a command on another node.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Redirect {
+    pub kind: RedirectKind,
+    pub slot: u16,
+    /// `host:port` of the node to retry on.
+    pub addr: String,
+}
+
+/// Whether a [`Redirect`] is permanent or for a single command.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RedirectKind {
+    /// `MOVED`, update the slot map and retry.
+    Moved,
+    /// `ASK`, send `ASKING` then retry this command only.
+    Ask,
+}
+
+impl Display for RespError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.code.is_empty(), self.message.is_empty()) {
+            (true, _) => write!(f, "{}", self.message),
+            (false, true) => write!(f, "{}", self.code),
+            (false, false) => write!(f, "{} {}", self.code, self.message),
+        }
     }
 }
 
@@ -97,9 +297,29 @@ pub enum ErrorKind {
     TrailingCharacters,
     ExpectedSign,
     UnexpectedCR,
+    UnexpectedLF,
     UnexpectedSign,
     BulkStringOverflow,
+    InvalidSimpleString,
+    UnexpectedType,
+    ArrayTooLarge,
+    ArrayOverrun,
+    UnconsumedElements,
+    WrongTupleLength,
+    FrameTooLarge,
+    TooManyElements,
+    DepthLimitExceeded,
+    OutputTooLarge,
     WrongSizeOfBulkString,
+    InvalidBulkLength,
+    InvalidInteger,
+    IntegerOverflow,
+    InvalidCommandName,
+    UnknownField,
+    DuplicateKey,
+    RedisError,
+    ErrorReply,
+    Unsupported,
     FromUtf8Error,
     IoError,
     ParseIntError,
@@ -111,15 +331,88 @@ impl Error {
             Error::Message(_) => ErrorKind::Message,
             Error::Eof => ErrorKind::Eof,
             Error::Syntax(_) => ErrorKind::Syntax,
-            Error::TrailingCharacters => ErrorKind::TrailingCharacters,
+            Error::TrailingCharacters(_) => ErrorKind::TrailingCharacters,
             Error::ExpectedSign{..} => ErrorKind::ExpectedSign,
             Error::UnexpectedCR(_) => ErrorKind::UnexpectedCR,
+            Error::UnexpectedLF(_) => ErrorKind::UnexpectedLF,
             Error::UnexpectedSign {..} => ErrorKind::UnexpectedSign,
             Error::BulkStringOverflow => ErrorKind::BulkStringOverflow,
+            Error::InvalidSimpleString => ErrorKind::InvalidSimpleString,
+            Error::UnexpectedType{..} => ErrorKind::UnexpectedType,
+            Error::ArrayTooLarge{..} => ErrorKind::ArrayTooLarge,
+            Error::ArrayOverrun{..} => ErrorKind::ArrayOverrun,
+            Error::UnconsumedElements{..} => ErrorKind::UnconsumedElements,
+            Error::WrongTupleLength{..} => ErrorKind::WrongTupleLength,
+            Error::FrameTooLarge{..} => ErrorKind::FrameTooLarge,
+            Error::TooManyElements{..} => ErrorKind::TooManyElements,
+            Error::DepthLimitExceeded{..} => ErrorKind::DepthLimitExceeded,
+            Error::OutputTooLarge{..} => ErrorKind::OutputTooLarge,
             Error::WrongSizeOfBulkString{..} => ErrorKind::WrongSizeOfBulkString,
+            Error::InvalidBulkLength(_) => ErrorKind::InvalidBulkLength,
+            Error::InvalidInteger{..} => ErrorKind::InvalidInteger,
+            Error::IntegerOverflow{..} => ErrorKind::IntegerOverflow,
+            Error::InvalidCommandName(_) => ErrorKind::InvalidCommandName,
+            Error::UnknownField{..} => ErrorKind::UnknownField,
+            Error::DuplicateKey{..} => ErrorKind::DuplicateKey,
+            Error::RedisError(_) => ErrorKind::RedisError,
+            Error::ErrorReply(_) => ErrorKind::ErrorReply,
+            Error::Unsupported{..} => ErrorKind::Unsupported,
             Error::FromUtf8Error(_) => ErrorKind::FromUtf8Error,
-            Error::IoError(_) => ErrorKind::IoError,
-            Error::ParseIntError(_) => ErrorKind::ParseIntError
+            Error::IoError{..} => ErrorKind::IoError,
+            Error::ParseIntError{..} => ErrorKind::ParseIntError
         }
     }
+
+    /// Offset in the input of the token the error was found at, for errors
+    /// that carry one.
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            Error::Syntax(pos)
+            | Error::TrailingCharacters(pos)
+            | Error::ExpectedSign(pos)
+            | Error::UnexpectedCR(pos)
+            | Error::UnexpectedLF(pos)
+            | Error::InvalidBulkLength(pos) => Some(pos),
+            Error::UnexpectedSign { pos, .. }
+            | Error::ArrayOverrun { pos, .. }
+            | Error::UnconsumedElements { pos, .. }
+            | Error::WrongTupleLength { pos, .. }
+            | Error::WrongSizeOfBulkString { pos, .. }
+            | Error::InvalidInteger { pos }
+            | Error::IntegerOverflow { pos }
+            | Error::UnknownField { pos, .. }
+            | Error::DuplicateKey { pos, .. }
+            | Error::ParseIntError { pos, .. } => Some(pos),
+            _ => None,
+        }
+    }
+
+    /// Display the error followed by the input it was met in, escaped by
+    /// [`debug_frame`] from the offending offset on,
+    /// or from its start for errors without one.
+    ///
+    /// ```
+    /// use serde_resp::RESPType;
+    ///
+    /// let input = b"*2\r\n:1\r\n:x\xff\r\n";
+    /// let err = serde_resp::from_slice::<RESPType>(input).unwrap_err();
+    /// assert_eq!(err.with_input(input).to_string(), format!("{err}, at `x\\xff\\r\\n`"));
+    /// ```
+    pub fn with_input<'a>(&'a self, input: &'a [u8]) -> ErrorWithInput<'a> {
+        ErrorWithInput { err: self, input }
+    }
+}
+
+/// An [`Error`] displayed along with the input it was met in, made by
+/// [`Error::with_input`].
+pub struct ErrorWithInput<'a> {
+    err: &'a Error,
+    input: &'a [u8],
+}
+
+impl Display for ErrorWithInput<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let from = self.err.offset().unwrap_or(0).min(self.input.len());
+        write!(f, "{}, at `{}`", self.err, debug_frame(&self.input[from..]))
+    }
 }
@@ -0,0 +1,351 @@
+use crate::replies::ByteBuf;
+use crate::{Error, RESPType, Result};
+use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt::{Formatter, Write};
+
+/// Number of bytes of a buffer rendered by [`debug_frame`].
+pub const DEBUG_FRAME_LEN: usize = 256;
+
+/// Check that a reply is `+OK`, the confirmation most commands answer with.
+///
+/// An error reply is returned as `Error::RedisError` carrying its message,
+/// any other reply as `Error::UnexpectedType`.
+pub fn expect_ok(resp: &RESPType) -> Result<()> {
+    match resp {
+        RESPType::SimpleString(s) if s == "OK" => Ok(()),
+        RESPType::Error(msg) => Err(Error::RedisError(msg.clone())),
+        other => Err(Error::UnexpectedType { expected: "+OK", found: other.type_name() }),
+    }
+}
+
+/// Read a reply that is either an integer or nil, as those of `LPOS` or `ZRANK`.
+///
+/// Both the RESP2 null bulk string and the RESP3 null are nil. Any other
+/// reply is `Error::UnexpectedType`.
+pub fn decode_int_or_nil(resp: &RESPType) -> Result<Option<i64>> {
+    match resp {
+        RESPType::Integer(n) => Ok(Some(*n)),
+        RESPType::None | RESPType::Null => Ok(None),
+        other => Err(Error::UnexpectedType { expected: "Integer or nil", found: other.type_name() }),
+    }
+}
+
+/// A frame received by a subscribed connection, an array in RESP2 and a
+/// push frame in RESP3 whose first element tells its kind.
+///
+/// The kind is matched regardless of case. An unknown kind or a wrong number
+/// of elements is an error.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PubSubMessage {
+    /// `message`, published on a subscribed channel.
+    Message { channel: Vec<u8>, payload: Vec<u8> },
+    /// `pmessage`, published on a channel matching a subscribed pattern.
+    PMessage { pattern: Vec<u8>, channel: Vec<u8>, payload: Vec<u8> },
+    /// `smessage`, published on a subscribed shard channel.
+    SMessage { channel: Vec<u8>, payload: Vec<u8> },
+    /// `subscribe`, with the number of subscriptions of the connection.
+    Subscribe { channel: Vec<u8>, count: i64 },
+    /// `unsubscribe`, with no channel when there was no subscription left.
+    Unsubscribe { channel: Option<Vec<u8>>, count: i64 },
+    /// `psubscribe`
+    PSubscribe { pattern: Vec<u8>, count: i64 },
+    /// `punsubscribe`
+    PUnsubscribe { pattern: Option<Vec<u8>>, count: i64 },
+    /// `ssubscribe`
+    SSubscribe { channel: Vec<u8>, count: i64 },
+    /// `sunsubscribe`
+    SUnsubscribe { channel: Option<Vec<u8>>, count: i64 },
+    /// `pong`, the RESP2 reply to a `PING` sent while subscribed.
+    Pong { payload: Vec<u8> },
+}
+
+const PUBSUB_KINDS: &[&str] = &[
+    "message",
+    "pmessage",
+    "smessage",
+    "subscribe",
+    "unsubscribe",
+    "psubscribe",
+    "punsubscribe",
+    "ssubscribe",
+    "sunsubscribe",
+    "pong",
+];
+
+impl<'de> Deserialize<'de> for PubSubMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_seq(PubSubVisitor)
+    }
+}
+
+struct PubSubVisitor;
+
+impl<'de> Visitor<'de> for PubSubVisitor {
+    type Value = PubSubMessage;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a pub/sub message")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let kind = next_field::<_, ByteBuf>(&mut seq, 0, "a message kind")?.0.to_ascii_lowercase();
+        let kind = String::from_utf8_lossy(&kind).into_owned();
+        let arity = match kind.as_str() {
+            "pmessage" => 4,
+            "pong" => 2,
+            _ => 3,
+        };
+        let expected = format!("{arity} elements for {kind}");
+        let expected = expected.as_str();
+        let bytes = |seq: &mut A, index| next_field::<_, ByteBuf>(seq, index, expected).map(|b| b.0);
+        let nullable = |seq: &mut A, index| {
+            next_field::<_, Option<ByteBuf>>(seq, index, expected).map(|b| b.map(|b| b.0))
+        };
+        let count = |seq: &mut A| next_field::<_, i64>(seq, 2, expected);
+        let message = match kind.as_str() {
+            "message" => PubSubMessage::Message { channel: bytes(&mut seq, 1)?, payload: bytes(&mut seq, 2)? },
+            "pmessage" => PubSubMessage::PMessage {
+                pattern: bytes(&mut seq, 1)?,
+                channel: bytes(&mut seq, 2)?,
+                payload: bytes(&mut seq, 3)?,
+            },
+            "smessage" => PubSubMessage::SMessage { channel: bytes(&mut seq, 1)?, payload: bytes(&mut seq, 2)? },
+            "subscribe" => PubSubMessage::Subscribe { channel: bytes(&mut seq, 1)?, count: count(&mut seq)? },
+            "unsubscribe" => PubSubMessage::Unsubscribe { channel: nullable(&mut seq, 1)?, count: count(&mut seq)? },
+            "psubscribe" => PubSubMessage::PSubscribe { pattern: bytes(&mut seq, 1)?, count: count(&mut seq)? },
+            "punsubscribe" => PubSubMessage::PUnsubscribe { pattern: nullable(&mut seq, 1)?, count: count(&mut seq)? },
+            "ssubscribe" => PubSubMessage::SSubscribe { channel: bytes(&mut seq, 1)?, count: count(&mut seq)? },
+            "sunsubscribe" => PubSubMessage::SUnsubscribe { channel: nullable(&mut seq, 1)?, count: count(&mut seq)? },
+            "pong" => PubSubMessage::Pong { payload: bytes(&mut seq, 1)? },
+            _ => return Err(de::Error::unknown_variant(&kind, PUBSUB_KINDS)),
+        };
+        let mut len = arity;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            len += 1;
+        }
+        if len > arity {
+            return Err(de::Error::invalid_length(len, &expected));
+        }
+        Ok(message)
+    }
+}
+
+// Read the element at {index} of a message, which must have {expected} elements.
+fn next_field<'de, A, T>(seq: &mut A, index: usize, expected: &str) -> std::result::Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq.next_element()?.ok_or_else(|| de::Error::invalid_length(index, &expected))
+}
+
+/// Render raw protocol bytes for logging, see [`debug_frame_with`].
+/// At most [`DEBUG_FRAME_LEN`] bytes are rendered.
+pub fn debug_frame(buf: &[u8]) -> String {
+    debug_frame_with(buf, DEBUG_FRAME_LEN)
+}
+
+/// Render the first `max_len` bytes of `buf` as printable ASCII.
+///
+/// Printable ASCII is kept as is, `\r` and `\n` are escaped as such and other
+/// bytes as `\xHH`. A longer buffer is cut with `...` followed by its total length.
+///
+/// ```
+/// use serde_resp::helpers::debug_frame_with;
+///
+/// assert_eq!(debug_frame_with(b"$2\r\n\xff\x00\r\n", 64), "$2\\r\\n\\xff\\x00\\r\\n");
+/// assert_eq!(debug_frame_with(b"+PONG\r\n", 3), "+PO... (7 bytes)");
+/// ```
+pub fn debug_frame_with(buf: &[u8], max_len: usize) -> String {
+    let mut out = String::with_capacity(buf.len().min(max_len));
+    for &b in buf.iter().take(max_len) {
+        match b {
+            b'\r' => out.push_str("\\r"),
+            b'\n' => out.push_str("\\n"),
+            b' '..=b'~' => out.push(b as char),
+            _ => {
+                let _ = write!(out, "\\x{:02x}", b);
+            }
+        }
+    }
+    if buf.len() > max_len {
+        let _ = write!(out, "... ({} bytes)", buf.len());
+    }
+    out
+}
+
+/// Render a reply as plain text, e.g. for a log line.
+///
+/// Scalars are rendered as their text, bulk strings as lossy UTF-8 and nulls
+/// as nothing. The elements of arrays, and the keys and values of maps, are
+/// flattened recursively and joined with `sep`.
+///
+/// ```
+/// use serde_resp::{array, bulk, i64};
+/// use serde_resp::helpers::to_flat_string;
+/// use serde_resp::RESPType;
+///
+/// let resp = array!(bulk!("a"), array!(i64!(1), i64!(2)));
+/// assert_eq!(to_flat_string(&resp, " "), "a 1 2");
+/// ```
+pub fn to_flat_string(resp: &RESPType, sep: &str) -> String {
+    let mut out = String::new();
+    flatten_into(&mut out, resp, sep);
+    out
+}
+
+fn flatten_into(out: &mut String, resp: &RESPType, sep: &str) {
+    match resp {
+        RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => out.push_str(s),
+        RESPType::Integer(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        RESPType::UInteger(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        RESPType::Double(v) => {
+            let _ = write!(out, "{}", v);
+        }
+        RESPType::Boolean(b) => {
+            let _ = write!(out, "{}", b);
+        }
+        RESPType::BulkString(bytes) => out.push_str(&String::from_utf8_lossy(bytes)),
+        RESPType::None | RESPType::NullArray | RESPType::Null => {}
+        RESPType::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(sep);
+                }
+                flatten_into(out, element, sep);
+            }
+        }
+        RESPType::Map(pairs) => {
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(sep);
+                }
+                flatten_into(out, key, sep);
+                out.push_str(sep);
+                flatten_into(out, value, sep);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod helpers_test {
+    use crate::error::ErrorKind;
+    use crate::helpers::{debug_frame, debug_frame_with, decode_int_or_nil, to_flat_string};
+    use crate::helpers::PubSubMessage;
+    use crate::{expect_ok, from_str, Error, RESPType, Result};
+
+    #[test]
+    fn test_pubsub_message() -> Result<()> {
+        let message: PubSubMessage = from_str("*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n")?;
+        assert_eq!(message, PubSubMessage::Message { channel: b"news".to_vec(), payload: b"hello".to_vec() });
+        let pushed: PubSubMessage = from_str(">3\r\n$7\r\nMESSAGE\r\n$4\r\nnews\r\n$5\r\nhello\r\n")?;
+        assert_eq!(pushed, message);
+        assert_eq!(
+            from_str::<PubSubMessage>(">4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$2\r\nhi\r\n")?,
+            PubSubMessage::PMessage { pattern: b"n*".to_vec(), channel: b"news".to_vec(), payload: b"hi".to_vec() }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*3\r\n$8\r\nsmessage\r\n$2\r\n{a\r\n$0\r\n\r\n")?,
+            PubSubMessage::SMessage { channel: b"{a".to_vec(), payload: vec![] }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n")?,
+            PubSubMessage::Subscribe { channel: b"news".to_vec(), count: 1 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n")?,
+            PubSubMessage::Unsubscribe { channel: None, count: 0 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>(">3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:2\r\n")?,
+            PubSubMessage::PSubscribe { pattern: b"n*".to_vec(), count: 2 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>(">3\r\n$12\r\npunsubscribe\r\n$2\r\nn*\r\n:1\r\n")?,
+            PubSubMessage::PUnsubscribe { pattern: Some(b"n*".to_vec()), count: 1 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*3\r\n$10\r\nssubscribe\r\n$2\r\n{a\r\n:1\r\n")?,
+            PubSubMessage::SSubscribe { channel: b"{a".to_vec(), count: 1 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*3\r\n$12\r\nsunsubscribe\r\n$2\r\n{a\r\n:0\r\n")?,
+            PubSubMessage::SUnsubscribe { channel: Some(b"{a".to_vec()), count: 0 }
+        );
+        assert_eq!(
+            from_str::<PubSubMessage>("*2\r\n$4\r\npong\r\n$0\r\n\r\n")?,
+            PubSubMessage::Pong { payload: vec![] }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pubsub_message_malformed() -> Result<()> {
+        let err = from_str::<PubSubMessage>("*2\r\n$7\r\nmessage\r\n$4\r\nnews\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid length 2, expected 3 elements for message");
+        let err = from_str::<PubSubMessage>("*4\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n:2\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid length 4, expected 3 elements for subscribe");
+        let err = from_str::<PubSubMessage>("*2\r\n$5\r\nhello\r\n$1\r\na\r\n").unwrap_err();
+        assert!(err.to_string().starts_with("unknown variant `hello`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_int_or_nil() -> Result<()> {
+        assert_eq!(decode_int_or_nil(&RESPType::Integer(3))?, Some(3));
+        assert_eq!(decode_int_or_nil(&RESPType::None)?, None);
+        assert_eq!(decode_int_or_nil(&RESPType::Null)?, None);
+        assert!(
+            decode_int_or_nil(&RESPType::BulkString(b"3".to_vec()))
+                .is_err_and(|err| err.kind() == ErrorKind::UnexpectedType)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_frame() -> Result<()> {
+        assert_eq!(
+            debug_frame(b"*2\r\n$3\r\nSET\r\n$4\r\n\x00\x7f\xe9\t\r\n"),
+            r"*2\r\n$3\r\nSET\r\n$4\r\n\x00\x7f\xe9\x09\r\n"
+        );
+        assert_eq!(debug_frame(b""), "");
+        assert_eq!(debug_frame_with(b"+OK\r\n", 5), r"+OK\r\n");
+        assert_eq!(debug_frame_with(b"+OK\r\n", 4), r"+OK\r... (5 bytes)");
+        let long = vec![b'x'; 1000];
+        assert_eq!(debug_frame(&long), format!("{}... (1000 bytes)", "x".repeat(256)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_flat_string() -> Result<()> {
+        let resp: RESPType = from_str("*5\r\n+OK\r\n:-7\r\n$4\r\nb\u{e9}r\r\n*2\r\n$1\r\nx\r\n$-1\r\n-ERR no\r\n")?;
+        assert_eq!(to_flat_string(&resp, ","), "OK,-7,b\u{e9}r,x,,ERR no");
+        let map: RESPType = from_str("%2\r\n+a\r\n:1\r\n+b\r\n*0\r\n")?;
+        assert_eq!(to_flat_string(&map, ", "), "a, 1, b, ");
+        assert_eq!(to_flat_string(&RESPType::BulkString(vec![b'a', 0xff]), ","), "a\u{fffd}");
+        assert_eq!(to_flat_string(&RESPType::Integer(3), ","), "3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_expect_ok() -> Result<()> {
+        expect_ok(&RESPType::ok())?;
+        assert!(
+            expect_ok(&RESPType::Error("ERR syntax error".to_owned()))
+                .is_err_and(|err| matches!(err, Error::RedisError(msg) if msg == "ERR syntax error"))
+        );
+        for resp in [RESPType::SimpleString("QUEUED".to_owned()), RESPType::Integer(1), RESPType::None] {
+            assert!(expect_ok(&resp).is_err_and(|err| err.kind() == ErrorKind::UnexpectedType));
+        }
+        Ok(())
+    }
+}
@@ -1,31 +1,544 @@
-#![feature(is_some_and)]
 extern crate core;
 
+pub mod command;
+#[cfg(feature = "commands")]
+pub mod commands;
+mod convert;
 pub mod de;
+pub mod decode;
+pub mod encode;
 pub mod error;
+pub mod helpers;
+mod limits;
+pub mod parser;
+pub mod replies;
+mod reply;
 pub mod ser;
+mod simple_str;
+#[cfg(feature = "smallvec")]
+pub mod small_bytes;
 pub mod marco;
+mod pair;
+mod read;
+pub mod value;
 
-pub use crate::error::{Error, Result};
-pub use crate::resp_type::RESPType;
+pub use crate::command::{normalized_command, Case, CommandBuilder};
+pub use crate::decode::RespDecode;
+pub use crate::encode::RespEncode;
+pub use crate::error::{ConvertError, Error, ErrorWithInput, Redirect, RedirectKind, RespError, RespErrorKind, Result};
+pub use crate::limits::Limits;
+pub use crate::pair::Pair;
+pub use crate::parser::{frame_boundaries, Parser};
+pub use crate::reply::Reply;
+pub use crate::resp_type::{ArrayBuilder, RESPType, RespKind, RespVersion};
+pub use crate::simple_str::SimpleStr;
 
-pub use crate::de::{from_str, from_reader};
-pub use crate::ser::{to_string, to_writer};
+pub use crate::de::{from_str, from_string, from_slice, from_reader, from_bufread, from_str_with_raw};
+pub use crate::de::{from_str_partial, from_slice_partial, from_reader_pipeline_bounded};
+pub use crate::de::{from_str_with, from_slice_with, from_reader_with, peek_type, array_len, DeserializerBuilder};
+pub use crate::de::{DuplicateKeyPolicy, LineEnding, StringMode};
+pub use crate::ser::{to_string, to_vec, to_writer};
+pub use crate::ser::{to_vec_with, to_writer_with, to_writer_pipeline, transcode, write_null, SerializerBuilder};
+pub use crate::ser::to_writer_pipeline_with;
+pub use crate::ser::{encode_command_str, encode_command_str_with, write_bulk_from_reader, write_bulk_from_reader_with};
+pub use crate::helpers::{debug_frame, decode_int_or_nil, expect_ok, to_flat_string, PubSubMessage};
+pub use crate::value::{from_value, to_value};
 
 pub mod resp_type {
-    #[derive(Debug, Eq, PartialEq)]
+    use crate::{CommandBuilder, ConvertError, Error, Redirect, RespDecode, RespError, Result};
+    use std::time::Duration;
+
+    /// A RESP value.
+    ///
+    /// Doubles compare by their bits, so that `RESPType` is `Eq`: a NaN equals
+    /// itself and `0.0` differs from `-0.0`.
+    #[derive(Debug, Clone)]
     pub enum RESPType {
         SimpleString(String),
         Integer(i64),
+        /// Integer above `i64::MAX`, only read with `wide_integers`.
+        UInteger(u64),
+        /// RESP3 double, `,<value>\r\n`, possibly `inf`, `-inf` or `nan`.
+        /// It is written as a bulk string in RESP2.
+        Double(f64),
+        /// RESP3 boolean, `#t\r\n` or `#f\r\n`. It is written as `:1` or `:0` in RESP2.
+        Boolean(bool),
         Error(String),
         BulkString(Vec<u8>),
         Array(Vec<RESPType>),
-        None
+        /// Null bulk string, `$-1\r\n`.
+        None,
+        /// Null array, `*-1\r\n`.
+        NullArray,
+        /// RESP3 null, `_\r\n`.
+        Null,
+        /// RESP3 big number, `(<digits>\r\n`, kept as its decimal digits.
+        BigNumber(String),
+        /// RESP3 map, `%<pairs>\r\n` followed by alternating keys and values.
+        /// It is written as a flat array of keys and values in RESP2.
+        Map(Vec<(RESPType, RESPType)>),
+    }
+
+    impl PartialEq for RESPType {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (RESPType::SimpleString(a), RESPType::SimpleString(b)) => a == b,
+                (RESPType::Integer(a), RESPType::Integer(b)) => a == b,
+                (RESPType::UInteger(a), RESPType::UInteger(b)) => a == b,
+                (RESPType::Double(a), RESPType::Double(b)) => a.to_bits() == b.to_bits(),
+                (RESPType::Boolean(a), RESPType::Boolean(b)) => a == b,
+                (RESPType::Error(a), RESPType::Error(b)) => a == b,
+                (RESPType::BulkString(a), RESPType::BulkString(b)) => a == b,
+                (RESPType::Array(a), RESPType::Array(b)) => a == b,
+                (RESPType::None, RESPType::None) => true,
+                (RESPType::NullArray, RESPType::NullArray) => true,
+                (RESPType::Null, RESPType::Null) => true,
+                (RESPType::BigNumber(a), RESPType::BigNumber(b)) => a == b,
+                (RESPType::Map(a), RESPType::Map(b)) => a == b,
+                _ => false,
+            }
+        }
+    }
+
+    impl Eq for RESPType {}
+
+    /// Version of the protocol spoken on a connection.
+    #[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+    pub enum RespVersion {
+        #[default]
+        Resp2,
+        Resp3,
+    }
+
+    /// Type of a frame, as told by its leading byte.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub enum RespKind {
+        /// `+`
+        SimpleString,
+        /// `-`
+        Error,
+        /// `:`
+        Integer,
+        /// `$`
+        BulkString,
+        /// `*`
+        Array,
+        /// `_`
+        Null,
+        /// `#`
+        Boolean,
+        /// `,`
+        Double,
+        /// `(`
+        BigNumber,
+        /// `!`
+        BulkError,
+        /// `=`
+        VerbatimString,
+        /// `%`
+        Map,
+        /// `~`
+        Set,
+        /// `|`
+        Attribute,
+        /// `>`
+        Push,
+    }
+
+    impl RespKind {
+        /// The kind of frame starting with `prefix`, if any.
+        pub fn from_prefix(prefix: u8) -> Option<RespKind> {
+            let kind = match prefix {
+                b'+' => RespKind::SimpleString,
+                b'-' => RespKind::Error,
+                b':' => RespKind::Integer,
+                b'$' => RespKind::BulkString,
+                b'*' => RespKind::Array,
+                b'_' => RespKind::Null,
+                b'#' => RespKind::Boolean,
+                b',' => RespKind::Double,
+                b'(' => RespKind::BigNumber,
+                b'!' => RespKind::BulkError,
+                b'=' => RespKind::VerbatimString,
+                b'%' => RespKind::Map,
+                b'~' => RespKind::Set,
+                b'|' => RespKind::Attribute,
+                b'>' => RespKind::Push,
+                _ => return None,
+            };
+            Some(kind)
+        }
     }
 
     impl RESPType {
         pub fn ok() -> RESPType {
             RESPType::SimpleString("OK".to_owned())
         }
+
+        /// Start building the command `name`, see [`CommandBuilder`].
+        pub fn command(name: &str) -> CommandBuilder {
+            CommandBuilder::new(name)
+        }
+
+        /// The nil reply of the given protocol version.
+        pub fn null(version: RespVersion) -> RESPType {
+            match version {
+                RespVersion::Resp2 => RESPType::None,
+                RespVersion::Resp3 => RESPType::Null,
+            }
+        }
+
+        /// A Unix timestamp in milliseconds, as taken by `PEXPIREAT` and `SET PXAT`.
+        pub fn from_unix_millis(millis: i64) -> RESPType {
+            RESPType::Integer(millis)
+        }
+
+        /// Convert into `T` with [`RespDecode`].
+        pub fn decode<T: RespDecode>(&self) -> Result<T> {
+            T::decode(self)
+        }
+
+        /// The value of a big number, or of an integer.
+        #[cfg(feature = "bigint")]
+        pub fn as_bigint(&self) -> Result<num_bigint::BigInt> {
+            match self {
+                RESPType::Integer(n) => Ok((*n).into()),
+                RESPType::UInteger(n) => Ok((*n).into()),
+                RESPType::BigNumber(digits) => digits.parse().map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(digits), &"a big number")
+                }),
+                other => Err(Error::UnexpectedType { expected: "BigNumber", found: other.type_name() }),
+            }
+        }
+
+        /// Map a reply to an HTTP status and body, for gateways exposing Redis over HTTP.
+        ///
+        /// An error reply is `500` with its message, a null of any kind `404`
+        /// with an empty body, and anything else `200`. The body of a string,
+        /// integer or big number is its text, that of an array or map its RESP
+        /// encoding.
+        #[cfg(feature = "http")]
+        pub fn to_http_status(&self) -> (u16, String) {
+            match self {
+                RESPType::Error(msg) => (500, msg.clone()),
+                RESPType::None | RESPType::NullArray | RESPType::Null => (404, String::new()),
+                RESPType::SimpleString(s) | RESPType::BigNumber(s) => (200, s.clone()),
+                RESPType::Integer(n) => (200, n.to_string()),
+                RESPType::UInteger(n) => (200, n.to_string()),
+                RESPType::Double(v) => (200, v.to_string()),
+                RESPType::Boolean(b) => (200, b.to_string()),
+                RESPType::BulkString(bytes) => (200, String::from_utf8_lossy(bytes).into_owned()),
+                RESPType::Array(_) | RESPType::Map(_) => {
+                    (200, crate::to_vec(self).map(|frame| String::from_utf8_lossy(&frame).into_owned()).unwrap_or_default())
+                }
+            }
+        }
+
+        /// Convert the elements of an array, reporting the index of the first
+        /// one that fails to convert.
+        ///
+        /// A null array, or a RESP3 null, is an empty `Vec` if `null_as_empty`
+        /// is set, and an error otherwise.
+        pub fn try_into_vec<T>(self, null_as_empty: bool) -> std::result::Result<Vec<T>, ConvertError>
+        where
+            T: TryFrom<RESPType, Error = ConvertError>,
+        {
+            match self {
+                RESPType::Array(arr) => arr
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, elem)| T::try_from(elem).map_err(|err| ConvertError { index: Some(i), ..err }))
+                    .collect(),
+                RESPType::NullArray | RESPType::Null if null_as_empty => Ok(Vec::new()),
+                other => Err(ConvertError { index: None, expected: "Array", found: other.type_name() }),
+            }
+        }
+
+        /// Like `try_into_vec`, converting from borrowed elements.
+        pub fn to_vec_of<T>(&self, null_as_empty: bool) -> std::result::Result<Vec<T>, ConvertError>
+        where
+            T: for<'a> TryFrom<&'a RESPType, Error = ConvertError>,
+        {
+            match self {
+                RESPType::Array(arr) => arr
+                    .iter()
+                    .enumerate()
+                    .map(|(i, elem)| T::try_from(elem).map_err(|err| ConvertError { index: Some(i), ..err }))
+                    .collect(),
+                RESPType::NullArray | RESPType::Null if null_as_empty => Ok(Vec::new()),
+                other => Err(ConvertError { index: None, expected: "Array", found: other.type_name() }),
+            }
+        }
+
+        /// Concatenate the elements of two arrays into a new array.
+        pub fn concat(self, other: RESPType) -> Result<RESPType> {
+            match (self, other) {
+                (RESPType::Array(mut arr), RESPType::Array(other)) => {
+                    arr.extend(other);
+                    Ok(RESPType::Array(arr))
+                }
+                (RESPType::Array(_), other) => Err(Error::UnexpectedType {
+                    expected: "Array",
+                    found: other.type_name(),
+                }),
+                (this, _) => Err(Error::UnexpectedType {
+                    expected: "Array",
+                    found: this.type_name(),
+                }),
+            }
+        }
+
+        /// The cluster redirection of a `MOVED` or `ASK` error reply, `None` for
+        /// any other reply.
+        pub fn redirect(&self) -> Option<Redirect> {
+            match self {
+                RESPType::Error(msg) => RespError::parse(msg).redirect(),
+                _ => None,
+            }
+        }
+
+        /// Push an element to the end of an array.
+        pub fn append(&mut self, element: RESPType) -> Result<()> {
+            match self {
+                RESPType::Array(arr) => {
+                    arr.push(element);
+                    Ok(())
+                }
+                _ => Err(Error::UnexpectedType {
+                    expected: "Array",
+                    found: self.type_name(),
+                }),
+            }
+        }
+
+        /// The element reached by indexing nested arrays with each index of `path`
+        /// in turn, `None` if an index is out of range or a level is no array.
+        /// An empty path is the value itself.
+        pub fn get_path(&self, path: &[usize]) -> Option<&RESPType> {
+            path.iter().try_fold(self, |value, &index| match value {
+                RESPType::Array(arr) => arr.get(index),
+                _ => None,
+            })
+        }
+
+        /// The name of the variant, as used in error messages.
+        pub fn type_name(&self) -> &'static str {
+            match self {
+                RESPType::SimpleString(_) => "SimpleString",
+                RESPType::Integer(_) => "Integer",
+                RESPType::UInteger(_) => "UInteger",
+                RESPType::Double(_) => "Double",
+                RESPType::Boolean(_) => "Boolean",
+                RESPType::Error(_) => "Error",
+                RESPType::BulkString(_) => "BulkString",
+                RESPType::Array(_) => "Array",
+                RESPType::None => "None",
+                RESPType::NullArray => "NullArray",
+                RESPType::Null => "Null",
+                RESPType::BigNumber(_) => "BigNumber",
+                RESPType::Map(_) => "Map",
+            }
+        }
+    }
+
+    /// Builder of possibly nested `RESPType::Array`s.
+    ///
+    /// ```
+    /// use serde_resp::{ArrayBuilder, RESPType};
+    ///
+    /// let arr = ArrayBuilder::new()
+    ///     .push(RESPType::Integer(1))
+    ///     .push_array(|b| b.push(RESPType::Integer(2)))
+    ///     .build();
+    /// assert_eq!(
+    ///     arr,
+    ///     RESPType::Array(vec![RESPType::Integer(1), RESPType::Array(vec![RESPType::Integer(2)])])
+    /// );
+    /// ```
+    #[derive(Debug, Clone, Default, Eq, PartialEq)]
+    pub struct ArrayBuilder {
+        elements: Vec<RESPType>,
+    }
+
+    impl ArrayBuilder {
+        pub fn new() -> Self {
+            ArrayBuilder::default()
+        }
+
+        /// Append an element.
+        pub fn push(mut self, element: RESPType) -> Self {
+            self.elements.push(element);
+            self
+        }
+
+        /// Append an array, built by `f` from an empty builder.
+        pub fn push_array<F>(self, f: F) -> Self
+        where
+            F: FnOnce(ArrayBuilder) -> ArrayBuilder,
+        {
+            self.push(f(ArrayBuilder::new()).build())
+        }
+
+        pub fn build(self) -> RESPType {
+            RESPType::Array(self.elements)
+        }
+    }
+
+    /// A duration is its number of milliseconds, as taken by `PEXPIRE` and `SET PX`.
+    /// Durations too long for an `i64` saturate.
+    impl From<Duration> for RESPType {
+        fn from(duration: Duration) -> Self {
+            RESPType::Integer(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+        }
+    }
+
+    #[cfg(test)]
+    mod resp_type_test {
+        use crate::error::ErrorKind;
+        use crate::{array, bulk, err, i64, simple};
+        use crate::{to_string, ArrayBuilder, RESPType, Redirect, RedirectKind, Result};
+        use std::time::Duration;
+
+        #[cfg(feature = "bigint")]
+        #[test]
+        fn test_as_bigint() -> Result<()> {
+            let digits = "12345678901234567890123456789012345678901234567890";
+            let big = crate::from_str::<RESPType>(&format!("({digits}\r\n"))?.as_bigint()?;
+            assert_eq!(big.to_string(), digits);
+            assert_eq!((big + 1u32).to_string(), "12345678901234567890123456789012345678901234567891");
+            assert_eq!(i64!(-7).as_bigint()?, (-7).into());
+            assert!(simple!("1".to_owned()).as_bigint().is_err_and(|err| err.kind() == ErrorKind::UnexpectedType));
+            Ok(())
+        }
+
+        #[cfg(feature = "http")]
+        #[test]
+        fn test_to_http_status() -> Result<()> {
+            assert_eq!(RESPType::ok().to_http_status(), (200, "OK".to_owned()));
+            let err = crate::from_str::<RESPType>("-ERR unknown command 'foo'\r\n")?;
+            assert_eq!(err.to_http_status(), (500, "ERR unknown command 'foo'".to_owned()));
+            assert_eq!(RESPType::None.to_http_status(), (404, String::new()));
+            assert_eq!(RESPType::NullArray.to_http_status().0, 404);
+            assert_eq!(RESPType::Null.to_http_status().0, 404);
+            assert_eq!(bulk!("value").to_http_status(), (200, "value".to_owned()));
+            assert_eq!(i64!(42).to_http_status(), (200, "42".to_owned()));
+            assert_eq!(array!(i64!(1)).to_http_status(), (200, "*1\r\n:1\r\n".to_owned()));
+            Ok(())
+        }
+
+        #[test]
+        fn test_type_name() -> Result<()> {
+            let names = [
+                (simple!("OK".to_owned()), "SimpleString"),
+                (i64!(1), "Integer"),
+                (RESPType::UInteger(u64::MAX), "UInteger"),
+                (RESPType::Double(0.5), "Double"),
+                (RESPType::Boolean(true), "Boolean"),
+                (err!("ERR".to_owned()), "Error"),
+                (bulk!("a"), "BulkString"),
+                (array!(), "Array"),
+                (RESPType::None, "None"),
+                (RESPType::NullArray, "NullArray"),
+                (RESPType::Null, "Null"),
+                (RESPType::BigNumber("1".to_owned()), "BigNumber"),
+                (RESPType::Map(vec![]), "Map"),
+            ];
+            for (resp, name) in names {
+                assert_eq!(resp.type_name(), name);
+            }
+            let err = String::try_from(i64!(1)).unwrap_err();
+            assert_eq!(err.to_string(), "expected String, found Integer");
+            Ok(())
+        }
+
+        #[test]
+        fn test_redirect() -> Result<()> {
+            let moved = err!("MOVED 3999 127.0.0.1:6381".to_owned());
+            let redirect = Redirect { kind: RedirectKind::Moved, slot: 3999, addr: "127.0.0.1:6381".to_owned() };
+            assert_eq!(moved.redirect(), Some(redirect));
+            let ask = crate::from_str::<RESPType>("-ASK 12182 10.0.0.2:7002\r\n")?;
+            let redirect = Redirect { kind: RedirectKind::Ask, slot: 12182, addr: "10.0.0.2:7002".to_owned() };
+            assert_eq!(ask.redirect(), Some(redirect));
+            assert_eq!(err!("ERR unknown command".to_owned()).redirect(), None);
+            assert_eq!(err!("MOVED soon".to_owned()).redirect(), None);
+            assert_eq!(simple!("MOVED 1 a:1".to_owned()).redirect(), None);
+            Ok(())
+        }
+
+        #[test]
+        fn test_concat() -> Result<()> {
+            let arr = array!(i64!(1), i64!(2)).concat(array!(simple!("three".to_owned())))?;
+            assert_eq!(arr, array!(i64!(1), i64!(2), simple!("three".to_owned())));
+            assert!(
+                array!(i64!(1)).concat(i64!(2))
+                    .is_err_and(|err| err.kind() == ErrorKind::UnexpectedType)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_get_path() -> Result<()> {
+            let resp = array!(array!(i64!(1), i64!(2), bulk!("leaf")), i64!(3));
+            assert_eq!(resp.get_path(&[0, 2]), Some(&bulk!("leaf")));
+            assert_eq!(resp.get_path(&[1]), Some(&i64!(3)));
+            assert_eq!(resp.get_path(&[]), Some(&resp));
+            assert_eq!(resp.get_path(&[0, 3]), None);
+            assert_eq!(resp.get_path(&[1, 0]), None);
+            assert_eq!(resp.get_path(&[2]), None);
+            Ok(())
+        }
+
+        #[test]
+        fn test_try_into_vec() -> Result<()> {
+            let ints = array!(i64!(1), i64!(2), i64!(3));
+            assert_eq!(ints.to_vec_of::<i64>(false), Ok(vec![1, 2, 3]));
+            assert_eq!(ints.try_into_vec::<i64>(false), Ok(vec![1, 2, 3]));
+            let mixed = array!(i64!(1), i64!(2), i64!(3), simple!("OK".to_owned()));
+            let err = mixed.try_into_vec::<i64>(false).unwrap_err();
+            assert_eq!(err.index, Some(3));
+            assert_eq!(err.to_string(), "expected Integer, found SimpleString at index 3");
+            // strings holding an integer convert, as with `RespDecode`
+            let digits = array!(i64!(1), simple!("2".to_owned()), bulk!("-3"));
+            assert_eq!(digits.to_vec_of::<i64>(false), Ok(vec![1, 2, -3]));
+            assert_eq!(digits.try_into_vec::<i64>(false), Ok(vec![1, 2, -3]));
+            let strings = array!(simple!("a".to_owned()), bulk!("b"));
+            assert_eq!(strings.to_vec_of::<String>(false), Ok(vec!["a".to_owned(), "b".to_owned()]));
+            assert_eq!(RESPType::NullArray.try_into_vec::<i64>(true), Ok(vec![]));
+            let err = RESPType::NullArray.to_vec_of::<i64>(false).unwrap_err();
+            assert_eq!((err.index, err.expected), (None, "Array"));
+            Ok(())
+        }
+
+        #[test]
+        fn test_array_builder() -> Result<()> {
+            let arr = ArrayBuilder::new()
+                .push(bulk!("SET"))
+                .push_array(|b| b.push(i64!(1)).push_array(|b| b).push(simple!("two".to_owned())))
+                .build();
+            assert_eq!(arr, array!(bulk!("SET"), array!(i64!(1), array!(), simple!("two".to_owned()))));
+            assert_eq!(to_string(&arr)?, "*2\r\n$3\r\nSET\r\n*3\r\n:1\r\n*0\r\n+two\r\n");
+            Ok(())
+        }
+
+        #[test]
+        fn test_duration() -> Result<()> {
+            assert_eq!(to_string(&RESPType::from(Duration::from_millis(1500)))?, ":1500\r\n");
+            assert_eq!(RESPType::from(Duration::from_micros(2999)), i64!(2));
+            assert_eq!(RESPType::from(Duration::MAX), i64!(i64::MAX));
+            assert_eq!(to_string(&RESPType::from_unix_millis(1_700_000_000_000))?, ":1700000000000\r\n");
+            Ok(())
+        }
+
+        #[test]
+        fn test_append() -> Result<()> {
+            let mut arr = array!();
+            arr.append(i64!(1))?;
+            assert_eq!(arr, array!(i64!(1)));
+            assert!(
+                RESPType::None.append(i64!(1))
+                    .is_err_and(|err| err.kind() == ErrorKind::UnexpectedType)
+            );
+            Ok(())
+        }
     }
 }
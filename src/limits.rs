@@ -0,0 +1,103 @@
+/// Protocol max length of a bulk string payload.
+pub(crate) const MAX_BULK_STRING_SIZE: usize = 512 * 1024 * 1024;
+pub(crate) const DEFAULT_MAX_ARRAY_LEN: usize = 1024 * 1024;
+pub(crate) const DEFAULT_MAX_ELEMENTS: usize = 4 * 1024 * 1024;
+/// Nesting that values are read and written at without overflowing the stack
+/// of a thread, decoding and encoding being recursive.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Size limits shared by the serializer, the deserializer and the [`Parser`](crate::Parser),
+/// so that both ends of a connection can agree on what a valid frame is.
+///
+/// Each limit applies wherever it makes sense, as documented on its field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum payload length of a bulk string. Default is 512MB, the protocol max.
+    pub max_bulk_len: usize,
+    /// Maximum number of elements a single array may declare. Default is 1M.
+    pub max_array_len: usize,
+    /// Maximum number of bytes one top-level value may span. No limit by default.
+    /// Only enforced when deserializing.
+    pub max_frame_size: usize,
+    /// Maximum number of values one top-level value may contain, counting itself
+    /// and every nested value. Default is 4M. Only enforced when deserializing.
+    pub max_elements: usize,
+    /// Maximum nesting of arrays and maps, a top-level array being at depth 1.
+    /// Default is 128, deeper input would overflow the stack when decoded.
+    pub max_depth: usize,
+    /// Maximum number of bytes a serializer may write in total. No limit by default.
+    /// Only enforced when serializing.
+    pub max_output_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_bulk_len: MAX_BULK_STRING_SIZE,
+            max_array_len: DEFAULT_MAX_ARRAY_LEN,
+            max_frame_size: usize::MAX,
+            max_elements: DEFAULT_MAX_ELEMENTS,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_output_bytes: usize::MAX,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits for a server facing untrusted clients: 1MB bulk strings, 64K
+    /// element arrays, 16MB frames holding up to 1M values nested 32 deep,
+    /// and 64MB of output.
+    pub fn strict() -> Self {
+        Limits {
+            max_bulk_len: 1024 * 1024,
+            max_array_len: 64 * 1024,
+            max_frame_size: 16 * 1024 * 1024,
+            max_elements: 1024 * 1024,
+            max_depth: 32,
+            max_output_bytes: 64 * 1024 * 1024,
+        }
+    }
+
+    /// No limit but the default nesting depth, which guards the stack rather
+    /// than memory. For trusted input only.
+    pub fn permissive() -> Self {
+        Limits {
+            max_bulk_len: usize::MAX,
+            max_array_len: usize::MAX,
+            max_frame_size: usize::MAX,
+            max_elements: usize::MAX,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_output_bytes: usize::MAX,
+        }
+    }
+
+    pub fn set_max_bulk_len(&mut self, max_bulk_len: usize) -> &mut Self {
+        self.max_bulk_len = max_bulk_len;
+        self
+    }
+
+    pub fn set_max_array_len(&mut self, max_array_len: usize) -> &mut Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize) -> &mut Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn set_max_elements(&mut self, max_elements: usize) -> &mut Self {
+        self.max_elements = max_elements;
+        self
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn set_max_output_bytes(&mut self, max_output_bytes: usize) -> &mut Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+}
@@ -38,4 +38,12 @@ macro_rules! none {
     () => {
         RESPType::None
     };
-}
\ No newline at end of file
+}
+
+/// Start building a command from its name and arguments, see [`CommandBuilder`](crate::CommandBuilder).
+#[macro_export]
+macro_rules! cmd {
+    ($name: expr $(, $arg: expr)* $(,)?) => {
+        $crate::CommandBuilder::new($name)$(.arg($arg))*
+    };
+}
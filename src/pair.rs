@@ -0,0 +1,63 @@
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTupleStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+// Tuple struct name `Pair` uses to tell this crate's serializer and
+// deserializer to flatten it. Any other format sees a 2 element tuple struct.
+pub(crate) const PAIR_TOKEN: &str = "$serde_resp::Pair";
+
+/// A key and a value written as two consecutive elements of the enclosing
+/// array rather than as a nested array, for flat encodings such as the
+/// arguments of `HSET` or the reply of `HGETALL`.
+///
+/// `Pair` only flattens directly inside a sequence such as a `Vec`, and
+/// every element of that sequence must be a `Pair`, the array header then
+/// counting keys and values. A `Pair` anywhere else is an error.
+///
+/// ```
+/// use serde_resp::{from_slice, to_vec, Pair};
+///
+/// let fields = vec![Pair("a".to_owned(), 1), Pair("b".to_owned(), 2)];
+/// let frame = to_vec(&fields)?;
+/// assert_eq!(frame, b"*4\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n");
+/// assert_eq!(from_slice::<Vec<Pair<String, i64>>>(&frame)?, fields);
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Pair<K, V>(pub K, pub V);
+
+impl<K: Serialize, V: Serialize> Serialize for Pair<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple_struct(PAIR_TOKEN, 2)?;
+        tuple.serialize_field(&self.0)?;
+        tuple.serialize_field(&self.1)?;
+        tuple.end()
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for Pair<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_tuple_struct(PAIR_TOKEN, 2, PairVisitor(PhantomData))
+    }
+}
+
+struct PairVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for PairVisitor<K, V> {
+    type Value = Pair<K, V>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a key and a value")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let key = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(Pair(key, value))
+    }
+}
@@ -0,0 +1,395 @@
+use crate::de::{DeserializerBuilder, LineEnding, TYPE_PREFIXES};
+use crate::read::Framing;
+use crate::{Error, Limits, Result};
+use serde::de::DeserializeOwned;
+use std::ops::Range;
+
+/// Incremental parser for a sans-io design, decoding values out of a buffer
+/// that grows as bytes arrive.
+///
+/// The parser remembers how far it got into the frame at the front of its
+/// buffer, so each call to [`Parser::next_value`] only looks at the bytes fed
+/// since the previous one. Bulk payloads are skipped by their declared length
+/// rather than scanned, and a frame is only decoded once complete.
+///
+/// ```
+/// use serde_resp::{Parser, RESPType};
+///
+/// let mut parser = Parser::new();
+/// parser.feed(b"$5\r\nhel");
+/// assert_eq!(parser.next_value::<RESPType>()?, None);
+/// parser.feed(b"lo\r\n");
+/// assert_eq!(parser.next_value::<RESPType>()?, Some(RESPType::BulkString(b"hello".to_vec())));
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    buf: Vec<u8>,
+    config: DeserializerBuilder,
+    scanner: Scanner,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Create a parser decoding with the limits and parsing modes of `config`.
+    pub fn with_config(config: DeserializerBuilder) -> Self {
+        Parser { config, ..Parser::default() }
+    }
+
+    /// Create a parser enforcing `limits` with the default parsing modes.
+    pub fn with_limits(limits: Limits) -> Self {
+        Parser::with_config(DeserializerBuilder::new().with_limits(limits))
+    }
+
+    /// Append received bytes to the buffer.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Number of bytes received but not decoded yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Decode the next value if all of its bytes have arrived, `None` otherwise.
+    ///
+    /// The bytes of a decoded value are removed from the buffer, even when
+    /// decoding fails, so the parser can go on with the following frame.
+    /// An error met while looking for the end of a frame leaves the stream
+    /// out of sync though, the connection should be closed then.
+    pub fn next_value<T>(&mut self) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(end) = self.scanner.scan(&self.buf, &self.config.framing())? else {
+            return Ok(None);
+        };
+        let mut de = self.config.build(&self.buf[..end]);
+        let value = T::deserialize(&mut de).and_then(|value| de.end().map(|_| value));
+        drop(de);
+        self.buf.drain(..end);
+        self.scanner.reset(0);
+        value.map(Some)
+    }
+}
+
+/// Split a buffer of pipelined frames into the byte range of each complete
+/// top-level frame, stopping at the first incomplete one.
+///
+/// Frames are only scanned for their end, not decoded, so each range can then
+/// be decoded on its own, possibly on another thread.
+///
+/// ```
+/// use serde_resp::frame_boundaries;
+///
+/// let buf = b"+OK\r\n:1\r\n$3\r\nfo";
+/// assert_eq!(frame_boundaries(buf)?, [0..5, 5..9]);
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+pub fn frame_boundaries(buf: &[u8]) -> Result<Vec<Range<usize>>> {
+    let framing = Framing::default();
+    let mut ranges = Vec::new();
+    let mut scanner = Scanner::default();
+    while let Some(end) = scanner.scan(buf, &framing)? {
+        ranges.push(scanner.start..end);
+        scanner.reset(end);
+    }
+    Ok(ranges)
+}
+
+// Finds where the frame at the front of a growing buffer ends, remembering how
+// far it got between calls.
+#[derive(Debug, Default)]
+pub(crate) struct Scanner {
+    // Offset in buf of the frame being scanned.
+    start: usize,
+    // Offset in buf of the next byte to scan, possibly past the end of the
+    // bytes received while a bulk payload is being waited for.
+    pos: usize,
+    // Offset in buf up to which the current line is known not to end.
+    line_scanned: usize,
+    // Number of elements each open array is still waiting for.
+    pending: Vec<usize>,
+    // Offset in buf of the terminator of the last bulk payload, when it may be
+    // either "\r\n" or "\n" and pos is only known once it has arrived.
+    bulk_terminator: Option<usize>,
+    // Number of bytes looked at while searching for the end of lines.
+    #[cfg(test)]
+    examined: usize,
+    // Number of values met in the frame, itself included.
+    pub(crate) values: usize,
+}
+
+impl Scanner {
+    // Start over with the frame beginning at {start}.
+    fn reset(&mut self, start: usize) {
+        self.start = start;
+        self.pos = start;
+        self.line_scanned = start;
+        self.bulk_terminator = None;
+        self.values = 0;
+    }
+
+    // Resume scanning the frame at start, returning the offset of its end
+    // once complete.
+    pub(crate) fn scan(&mut self, buf: &[u8], framing: &Framing) -> Result<Option<usize>> {
+        let limits = &framing.limits;
+        loop {
+            if let Some(at) = self.bulk_terminator {
+                let Some(&first) = buf.get(at) else {
+                    return Ok(None);
+                };
+                self.pos = if first == b'\r' { at + 2 } else { at + 1 };
+                self.line_scanned = self.pos;
+                self.bulk_terminator = None;
+            }
+            if self.pos > self.start && self.pending.is_empty() {
+                return Ok(Some(self.pos).filter(|&end| end <= buf.len()));
+            }
+            if self.pos >= buf.len() {
+                return Ok(None);
+            }
+            let Some(line_end) = self.find_line_end(buf) else {
+                // nothing is buffered past the frame limit waiting for a line to end
+                self.check_frame_size(buf.len(), limits)?;
+                return Ok(None);
+            };
+            let prefix = buf[self.pos];
+            if line_end == self.pos {
+                return Err(Error::ExpectedSign(self.pos));
+            }
+            // the "\r" may be missing with lenient line endings
+            let header_end = if buf[line_end - 1] == b'\r' { line_end - 1 } else { line_end };
+            let header = &buf[(self.pos + 1).min(header_end)..header_end];
+            let next = line_end + 1;
+            self.check_frame_size(next, limits)?;
+            self.count_value(limits)?;
+            match prefix {
+                b'$' | b'!' | b'=' => {
+                    let len = parse_length(header, self.pos + 1)?;
+                    let len = usize::try_from(len).ok();
+                    if len.is_some_and(|len| len > limits.max_bulk_len) {
+                        return Err(Error::BulkStringOverflow);
+                    }
+                    let payload_end = len.map(|len| next.saturating_add(len));
+                    self.pos = match (payload_end, framing.no_bulk_terminator, framing.line_ending) {
+                        (None, ..) => next,
+                        (Some(end), true, _) => end,
+                        (Some(end), false, LineEnding::CrLf) => end.saturating_add(2),
+                        (Some(end), false, LineEnding::Lf) => end.saturating_add(1),
+                        (Some(end), false, LineEnding::Auto) => {
+                            self.bulk_terminator = Some(end);
+                            end
+                        }
+                    };
+                    // fail on the declared length, before its payload arrives
+                    self.check_frame_size(self.pos, limits)?;
+                    self.complete_value();
+                }
+                b'*' | b'%' | b'>' | b'~' | b'|' => {
+                    let len = parse_length(header, self.pos + 1)?;
+                    let declared = usize::try_from(len).unwrap_or(0);
+                    let declared = if matches!(prefix, b'%' | b'|') { declared.saturating_mul(2) } else { declared };
+                    if declared > limits.max_array_len {
+                        return Err(Error::ArrayTooLarge { declared, limit: limits.max_array_len });
+                    }
+                    // fail before waiting for elements that would exceed the budget anyway
+                    if self.values.saturating_add(declared) > limits.max_elements {
+                        return Err(Error::TooManyElements { limit: limits.max_elements });
+                    }
+                    self.pos = next;
+                    // an attribute is followed by the value it is about
+                    let pending = if prefix == b'|' { declared + 1 } else { declared };
+                    if pending == 0 {
+                        self.complete_value();
+                    } else if self.pending.len() >= limits.max_depth {
+                        return Err(Error::DepthLimitExceeded { limit: limits.max_depth });
+                    } else {
+                        self.pending.push(pending);
+                    }
+                }
+                _ if TYPE_PREFIXES.contains(&prefix) => {
+                    self.pos = next;
+                    self.complete_value();
+                }
+                _ => return Err(Error::ExpectedSign(self.pos)),
+            }
+        }
+    }
+
+    // Check that the frame would not span more than the limit if it ended at {end}.
+    fn check_frame_size(&self, end: usize, limits: &Limits) -> Result<()> {
+        if end - self.start > limits.max_frame_size {
+            return Err(Error::FrameTooLarge { limit: limits.max_frame_size });
+        }
+        Ok(())
+    }
+
+    fn count_value(&mut self, limits: &Limits) -> Result<()> {
+        self.values += 1;
+        if self.values > limits.max_elements {
+            return Err(Error::TooManyElements { limit: limits.max_elements });
+        }
+        Ok(())
+    }
+
+    // Find the "\n" ending the line at pos, resuming the search where the
+    // previous one stopped.
+    fn find_line_end(&mut self, buf: &[u8]) -> Option<usize> {
+        let from = self.line_scanned.max(self.pos);
+        let found = buf[from..].iter().position(|&b| b == b'\n');
+        let scanned = found.map_or(buf.len() - from, |i| i + 1);
+        #[cfg(test)]
+        {
+            self.examined += scanned;
+        }
+        self.line_scanned = from + scanned;
+        found.map(|i| from + i)
+    }
+
+    // Account for a value being complete in the innermost open array,
+    // closing the arrays it completes.
+    fn complete_value(&mut self) {
+        while let Some(remaining) = self.pending.last_mut() {
+            *remaining -= 1;
+            if *remaining > 0 {
+                return;
+            }
+            self.pending.pop();
+        }
+    }
+}
+
+fn parse_length(header: &[u8], pos: usize) -> Result<i64> {
+    String::from_utf8_lossy(header)
+        .parse::<i64>()
+        .map_err(|err| Error::ParseIntError { err, pos })
+}
+
+#[cfg(test)]
+mod parser_test {
+    use crate::error::ErrorKind;
+    use crate::parser::frame_boundaries;
+    use crate::{from_slice, DeserializerBuilder, Error, LineEnding, Limits, Parser, RESPType, Result};
+
+    // Feed {frames} a byte at a time, decoding every value as soon as it is complete.
+    fn decode_bytewise(parser: &mut Parser, frames: &[u8]) -> Result<Vec<RESPType>> {
+        let mut decoded = Vec::new();
+        for &b in frames {
+            parser.feed(&[b]);
+            if let Some(value) = parser.next_value::<RESPType>()? {
+                decoded.push(value);
+            }
+        }
+        Ok(decoded)
+    }
+
+    #[test]
+    fn test_resume_bulk_string() -> Result<()> {
+        let payload = vec![b'x'; 1024 * 1024];
+        let mut frame = format!("${}\r\n", payload.len()).into_bytes();
+        frame.extend_from_slice(&payload);
+        frame.extend_from_slice(b"\r\n");
+
+        let mut parser = Parser::new();
+        let mut decoded = None;
+        for chunk in frame.chunks(64 * 1024) {
+            assert!(decoded.is_none());
+            parser.feed(chunk);
+            decoded = parser.next_value::<RESPType>()?;
+        }
+        assert_eq!(decoded, Some(RESPType::BulkString(payload)));
+        // only the header line was searched, the payload was never rescanned
+        assert_eq!(parser.scanner.examined, 10);
+        assert_eq!(parser.buffered(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_array() -> Result<()> {
+        let frames = b"*2\r\n$3\r\nfoo\r\n*1\r\n:1\r\n+OK\r\n";
+        let mut parser = Parser::new();
+        let mut decoded = Vec::new();
+        for &b in frames {
+            parser.feed(&[b]);
+            if let Some(value) = parser.next_value::<RESPType>()? {
+                decoded.push(value);
+            }
+        }
+        assert_eq!(
+            decoded,
+            [
+                RESPType::Array(vec![
+                    RESPType::BulkString(b"foo".to_vec()),
+                    RESPType::Array(vec![RESPType::Integer(1)]),
+                ]),
+                RESPType::ok(),
+            ]
+        );
+        // every byte but the bulk payload was looked at once
+        assert_eq!(parser.scanner.examined, frames.len() - 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_boundaries() -> Result<()> {
+        let buf = b"*2\r\n$3\r\nfoo\r\n:1\r\n+OK\r\n$5\r\nhello\r\n*1\r\n";
+        let ranges = frame_boundaries(buf)?;
+        assert_eq!(ranges, [0..17, 17..22, 22..33]);
+        let values = ranges.into_iter().map(|range| from_slice(&buf[range])).collect::<Result<Vec<RESPType>>>()?;
+        assert_eq!(values[1], RESPType::ok());
+        assert!(matches!(frame_boundaries(b"+OK\r\n%x\r\n"), Err(Error::ParseIntError { pos: 6, .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_frame() -> Result<()> {
+        let mut parser = Parser::new();
+        parser.feed(b"$x\r\n");
+        assert!(parser.next_value::<RESPType>().is_err_and(|err| err.kind() == ErrorKind::ParseIntError));
+        let mut parser = Parser::new();
+        parser.feed(b":1\r\n+OK\r\n");
+        assert!(parser.next_value::<String>().is_err_and(|err| err.kind() == ErrorKind::ExpectedSign));
+        assert_eq!(parser.next_value::<String>()?, Some("OK".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_endings() -> Result<()> {
+        let foo = RESPType::BulkString(b"foo".to_vec());
+        let mut parser = Parser::with_config(DeserializerBuilder::new().line_ending(LineEnding::Lf));
+        parser.feed(b"$3\nfoo\n+OK\n");
+        assert_eq!(parser.next_value::<RESPType>()?, Some(foo.clone()));
+        assert_eq!(parser.next_value::<RESPType>()?, Some(RESPType::ok()));
+        assert_eq!(parser.buffered(), 0);
+
+        let mut parser = Parser::with_config(DeserializerBuilder::new().line_ending(LineEnding::Auto));
+        let frames = b"*2\r\n$3\r\nfoo\n$3\nfoo\r\n$0\n\n";
+        let decoded = decode_bytewise(&mut parser, frames)?;
+        assert_eq!(decoded, [RESPType::Array(vec![foo.clone(), foo]), RESPType::BulkString(vec![])]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_bulk_trailing_crlf() -> Result<()> {
+        let mut parser = Parser::with_config(DeserializerBuilder::new().no_bulk_trailing_crlf(true));
+        let decoded = decode_bytewise(&mut parser, b"$3\r\nfoo+OK\r\n$2\r\n\r\n")?;
+        assert_eq!(
+            decoded,
+            [RESPType::BulkString(b"foo".to_vec()), RESPType::ok(), RESPType::BulkString(b"\r\n".to_vec())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_permissive_limits() -> Result<()> {
+        let mut parser = Parser::with_limits(Limits::permissive());
+        parser.feed(b"$3\r\nfoo\r\n$-1\r\n");
+        assert_eq!(parser.next_value::<RESPType>()?, Some(RESPType::BulkString(b"foo".to_vec())));
+        assert_eq!(parser.next_value::<RESPType>()?, Some(RESPType::None));
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::de::LineEnding;
+use crate::{Error, Limits, Result};
+use std::io::{BufRead, Read};
+
+// How frames are delimited, as configured on a `DeserializerBuilder`.
+#[derive(Default)]
+pub(crate) struct Framing {
+    pub(crate) limits: Limits,
+    pub(crate) line_ending: LineEnding,
+    // Bulk payloads are not followed by a line terminator.
+    pub(crate) no_bulk_terminator: bool,
+}
+
+// Read the raw bytes of exactly one frame from `reader` and append them to `buf`.
+// Header lines are read with `read_until`, bulk payloads with an exact-size read,
+// so nothing beyond the frame is consumed from the reader.
+// The frame is not validated beyond what is needed to find its end and enforce the limits.
+pub(crate) fn read_frame<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> Result<()> {
+    read_frame_with(reader, buf, &Framing::default())
+}
+
+pub(crate) fn read_frame_with<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>, framing: &Framing) -> Result<()> {
+    let start = buf.len();
+    FrameReader { reader, buf, framing, start, depth: 0, elements: 0 }.read_value()
+}
+
+// Reads one frame, enforcing the limits of `framing` as it goes so that no more
+// than `max_frame_size` bytes are ever buffered.
+struct FrameReader<'a, R> {
+    reader: &'a mut R,
+    buf: &'a mut Vec<u8>,
+    framing: &'a Framing,
+    // Offset in buf at which the frame begins.
+    start: usize,
+    // Number of aggregates the value being read is nested in.
+    depth: usize,
+    // Number of values read or declared so far, the frame itself included.
+    elements: usize,
+}
+
+impl<R: BufRead> FrameReader<'_, R> {
+    fn read_value(&mut self) -> Result<()> {
+        let line_start = self.buf.len();
+        self.count_elements(1)?;
+        self.read_line()?;
+        match self.buf[line_start] {
+            b'+' | b'-' | b':' | b'_' | b'#' | b'(' | b',' => Ok(()),
+            b'$' | b'!' | b'=' => {
+                let len = parse_length(self.buf, line_start)?;
+                if len < 0 {
+                    return Ok(());
+                }
+                let len = usize::try_from(len).unwrap_or(usize::MAX);
+                if len > self.framing.limits.max_bulk_len {
+                    return Err(Error::BulkStringOverflow);
+                }
+                // a "\r\n" terminator is only known once its first byte is read
+                let terminator = match (self.framing.no_bulk_terminator, self.framing.line_ending) {
+                    (true, _) => 0,
+                    (false, LineEnding::CrLf) => 2,
+                    (false, _) => 1,
+                };
+                self.check_frame_size(len.saturating_add(terminator))?;
+                self.read_exact(len.saturating_add(terminator))?;
+                if terminator == 1 && self.framing.line_ending == LineEnding::Auto && self.buf.ends_with(b"\r") {
+                    self.check_frame_size(1)?;
+                    self.read_exact(1)?;
+                }
+                Ok(())
+            }
+            b'*' | b'>' | b'~' => {
+                let num = parse_length(self.buf, line_start)?;
+                if num < 0 {
+                    return Ok(());
+                }
+                let declared = usize::try_from(num).unwrap_or(usize::MAX);
+                self.read_elements(declared)
+            }
+            prefix @ (b'%' | b'|') => {
+                let pairs = parse_length(self.buf, line_start)?;
+                let declared = usize::try_from(pairs).unwrap_or(usize::MAX).saturating_mul(2);
+                self.read_elements(declared)?;
+                // an attribute is followed by the value it is about
+                if prefix == b'|' {
+                    self.read_value()?;
+                }
+                Ok(())
+            }
+            _ => Err(Error::ExpectedSign(line_start)),
+        }
+    }
+
+    // Read the {declared} elements of an aggregate whose header was just read.
+    fn read_elements(&mut self, declared: usize) -> Result<()> {
+        let limits = &self.framing.limits;
+        if declared > limits.max_array_len {
+            return Err(Error::ArrayTooLarge { declared, limit: limits.max_array_len });
+        }
+        // Fail before reading elements that would exceed the budget anyway.
+        if self.elements.saturating_add(declared) > limits.max_elements {
+            return Err(Error::TooManyElements { limit: limits.max_elements });
+        }
+        if self.depth >= limits.max_depth {
+            return Err(Error::DepthLimitExceeded { limit: limits.max_depth });
+        }
+        self.depth += 1;
+        for _ in 0..declared {
+            self.read_value()?;
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn count_elements(&mut self, count: usize) -> Result<()> {
+        self.elements = self.elements.saturating_add(count);
+        if self.elements > self.framing.limits.max_elements {
+            return Err(Error::TooManyElements { limit: self.framing.limits.max_elements });
+        }
+        Ok(())
+    }
+
+    // Check that {more} bytes still fit in the frame.
+    fn check_frame_size(&self, more: usize) -> Result<()> {
+        let limit = self.framing.limits.max_frame_size;
+        if (self.buf.len() - self.start).saturating_add(more) > limit {
+            return Err(Error::FrameTooLarge { limit });
+        }
+        Ok(())
+    }
+
+    // Read until "\n", which must follow "\r" unless the line ending allows it alone.
+    // Only a reader ending before the "\n" is an `Error::Eof`, a line running past
+    // the frame limit is an `Error::FrameTooLarge`.
+    fn read_line(&mut self) -> Result<()> {
+        let line_start = self.buf.len();
+        let limit = self.framing.limits.max_frame_size;
+        let room = limit.saturating_sub(line_start - self.start) as u64;
+        let read = (&mut *self.reader).take(room).read_until(b'\n', self.buf)?;
+        if !self.buf[line_start..].ends_with(b"\n") {
+            if read as u64 == room {
+                return Err(Error::FrameTooLarge { limit });
+            }
+            return Err(Error::Eof);
+        }
+        if self.framing.line_ending == LineEnding::CrLf && !self.buf[line_start..].ends_with(b"\r\n") {
+            return Err(Error::UnexpectedLF(self.buf.len() - 1));
+        }
+        Ok(())
+    }
+
+    // Read exactly {len} bytes.
+    fn read_exact(&mut self, len: usize) -> Result<()> {
+        let len = len as u64;
+        let read = (&mut *self.reader).take(len).read_to_end(self.buf)?;
+        if (read as u64) < len {
+            return Err(Error::Eof);
+        }
+        Ok(())
+    }
+}
+
+// Parse the length of the header line beginning at `start`.
+fn parse_length(buf: &[u8], start: usize) -> Result<i64> {
+    let line = buf[start + 1..].strip_suffix(b"\n").unwrap_or(&buf[start + 1..]);
+    let digits = line.strip_suffix(b"\r").unwrap_or(line);
+    let pos = start + 1;
+    String::from_utf8_lossy(digits)
+        .parse::<i64>()
+        .map_err(|err| Error::ParseIntError { err, pos })
+}
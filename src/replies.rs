@@ -0,0 +1,251 @@
+use crate::Pair;
+use serde::de::{self, SeqAccess, Unexpected, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+
+/// Reply of `SCAN`, `SSCAN`, `HSCAN` and `ZSCAN`: the cursor to resume from,
+/// `0` once done, and a batch of keys or members.
+///
+/// The cursor arrives as a bulk string of digits, it is also read from an integer.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ScanReply {
+    pub cursor: u64,
+    pub keys: Vec<Vec<u8>>,
+}
+
+/// An entry of a stream as returned by `XRANGE`, `XREVRANGE` and `XREAD`:
+/// its ID and its field/value pairs, sent as a flat array.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// A field and its value, as returned by `HRANDFIELD ... WITHVALUES` in a
+/// flat array. Like [`Pair`], it must be an element of a sequence.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct KeyValue {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl Serialize for ScanReply {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let keys: Vec<Bytes> = self.keys.iter().map(|key| Bytes(key)).collect();
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.cursor.to_string())?;
+        tuple.serialize_element(&keys)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ScanReply {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScanVisitor;
+
+        impl<'de> Visitor<'de> for ScanVisitor {
+            type Value = ScanReply;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a cursor and an array of keys")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let Cursor(cursor) = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let keys: Vec<ByteBuf> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(ScanReply { cursor, keys: keys.into_iter().map(|key| key.0).collect() })
+            }
+        }
+
+        deserializer.deserialize_tuple_struct("ScanReply", 2, ScanVisitor)
+    }
+}
+
+impl Serialize for StreamEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields: Vec<Pair<Bytes, Bytes>> = self.fields.iter().map(|(k, v)| Pair(Bytes(k), Bytes(v))).collect();
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.id)?;
+        tuple.serialize_element(&fields)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntryVisitor;
+
+        impl<'de> Visitor<'de> for EntryVisitor {
+            type Value = StreamEntry;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("an entry ID and an array of fields and values")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let id = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let fields: Vec<Pair<ByteBuf, ByteBuf>> =
+                    seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let fields = fields.into_iter().map(|Pair(k, v)| (k.0, v.0)).collect();
+                Ok(StreamEntry { id, fields })
+            }
+        }
+
+        deserializer.deserialize_tuple_struct("StreamEntry", 2, EntryVisitor)
+    }
+}
+
+impl Serialize for KeyValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Pair(Bytes(&self.key), Bytes(&self.value)).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Pair(key, value) = Pair::<ByteBuf, ByteBuf>::deserialize(deserializer)?;
+        Ok(KeyValue { key: key.0, value: value.0 })
+    }
+}
+
+// Serialized as a bulk string, where `Vec<u8>` would be an array of integers.
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+// Deserialized from a bulk or simple string.
+pub(crate) struct ByteBuf(pub(crate) Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteBufVisitor;
+
+        impl Visitor<'_> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v.as_bytes().to_vec()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v.into_bytes()))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+// A cursor of the `SCAN` family, an unsigned 64 bit integer sent as digits.
+struct Cursor(u64);
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CursorVisitor;
+
+        impl Visitor<'_> for CursorVisitor {
+            type Value = Cursor;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a cursor")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v).map(Cursor).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(Cursor(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map(Cursor).map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                let digits = std::str::from_utf8(v).map_err(|_| E::invalid_value(Unexpected::Bytes(v), &self))?;
+                self.visit_str(digits)
+            }
+        }
+
+        deserializer.deserialize_any(CursorVisitor)
+    }
+}
+
+#[cfg(test)]
+mod replies_test {
+    use crate::replies::{KeyValue, ScanReply, StreamEntry};
+    use crate::{from_slice, from_str, to_vec, Result};
+
+    #[test]
+    fn test_scan_reply() -> Result<()> {
+        let frame = b"*2\r\n$2\r\n17\r\n*3\r\n$5\r\nkey:1\r\n$6\r\nkey:12\r\n$5\r\nkey:8\r\n";
+        let reply: ScanReply = from_slice(frame)?;
+        assert_eq!(reply.cursor, 17);
+        assert_eq!(reply.keys, [b"key:1".to_vec(), b"key:12".to_vec(), b"key:8".to_vec()]);
+        assert_eq!(to_vec(&reply)?, frame);
+        let done: ScanReply = from_str("*2\r\n$1\r\n0\r\n*0\r\n")?;
+        assert_eq!(done, ScanReply { cursor: 0, keys: vec![] });
+        let wide: ScanReply = from_str("*2\r\n$20\r\n18446744073709551615\r\n*0\r\n")?;
+        assert_eq!(wide.cursor, u64::MAX);
+        assert_eq!(from_str::<ScanReply>("*2\r\n:5\r\n*0\r\n")?.cursor, 5);
+        assert!(from_str::<ScanReply>("*2\r\n$1\r\nx\r\n*0\r\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_entry() -> Result<()> {
+        let frame = concat!(
+            "*2\r\n",
+            "*2\r\n$15\r\n1526985054069-0\r\n*4\r\n$11\r\ntemperature\r\n$2\r\n36\r\n$8\r\nhumidity\r\n$2\r\n95\r\n",
+            "*2\r\n$15\r\n1526985054079-0\r\n*4\r\n$11\r\ntemperature\r\n$2\r\n37\r\n$8\r\nhumidity\r\n$2\r\n94\r\n",
+        );
+        let entries: Vec<StreamEntry> = from_str(frame)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "1526985054069-0");
+        assert_eq!(
+            entries[1].fields,
+            [(b"temperature".to_vec(), b"37".to_vec()), (b"humidity".to_vec(), b"94".to_vec())]
+        );
+        assert_eq!(to_vec(&entries)?, frame.as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_value() -> Result<()> {
+        let frame = b"*4\r\n$4\r\nhead\r\n$5\r\nheads\r\n$4\r\ntail\r\n$5\r\ntails\r\n";
+        let pairs: Vec<KeyValue> = from_slice(frame)?;
+        assert_eq!(
+            pairs,
+            [
+                KeyValue { key: b"head".to_vec(), value: b"heads".to_vec() },
+                KeyValue { key: b"tail".to_vec(), value: b"tails".to_vec() },
+            ]
+        );
+        assert_eq!(to_vec(&pairs)?, frame);
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{Error, RESPType, Result};
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+
+// Newtype name `Reply` uses to exchange already encoded frames with this
+// crate's serializer and deserializer. Any other format sees a newtype around bytes.
+pub(crate) const REPLY_TOKEN: &str = "$serde_resp::Reply";
+
+/// Any RESP2 or RESP3 value, kept as it was sent for a proxy to forward it
+/// faithfully.
+///
+/// Unlike [`RESPType`](crate::RESPType), every kind of frame has its own
+/// variant: the three nulls are told apart, doubles and big numbers keep their
+/// text, and strings their bytes. Serializing a `Reply` read with `from_slice`
+/// or `from_reader` writes back the very bytes it was read from, whatever the
+/// protocol version of the serializer, provided they were written the way Redis
+/// writes them: integers and lengths in decimal without a `+` or leading zeros,
+/// and lines ended by `\r\n`.
+///
+/// `to_value` and `from_value` go through a `RESPType` instead, so they only
+/// take the frames a `RESPType` can be read from, with the details above lost.
+///
+/// ```
+/// use serde_resp::{from_slice, to_vec, Reply};
+///
+/// let frame = b"|1\r\n+ttl\r\n:3600\r\n%1\r\n+key\r\n,1.50\r\n";
+/// let reply: Reply = from_slice(frame)?;
+/// assert_eq!(to_vec(&reply)?, frame);
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// `+`
+    SimpleString(Vec<u8>),
+    /// `-`
+    Error(Vec<u8>),
+    /// `:`
+    Integer(i64),
+    /// `$`
+    BulkString(Vec<u8>),
+    /// `$-1`
+    NullBulk,
+    /// `*`
+    Array(Vec<Reply>),
+    /// `*-1`
+    NullArray,
+    /// `_`
+    Null,
+    /// `#`
+    Boolean(bool),
+    /// `,`, kept as its text such as `1.50` or `-inf`.
+    Double(String),
+    /// `(`
+    BigNumber(String),
+    /// `!`
+    BulkError(Vec<u8>),
+    /// `=`, a string with a three letter format such as `txt`.
+    VerbatimString { format: [u8; 3], text: Vec<u8> },
+    /// `%`
+    Map(Vec<(Reply, Reply)>),
+    /// `~`
+    Set(Vec<Reply>),
+    /// `|`, attributes followed by the reply they are about.
+    Attribute { attributes: Vec<(Reply, Reply)>, reply: Box<Reply> },
+    /// `>`
+    Push(Vec<Reply>),
+}
+
+impl Reply {
+    /// Encode the reply as it was received.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+        buf
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            Reply::SimpleString(s) => write_line(buf, b'+', s),
+            Reply::Error(s) => write_line(buf, b'-', s),
+            Reply::Integer(n) => write_line(buf, b':', n.to_string().as_bytes()),
+            Reply::BulkString(s) => write_bulk(buf, b'$', s),
+            Reply::NullBulk => buf.extend_from_slice(b"$-1\r\n"),
+            Reply::Array(arr) => write_aggregate(buf, b'*', arr),
+            Reply::NullArray => buf.extend_from_slice(b"*-1\r\n"),
+            Reply::Null => buf.extend_from_slice(b"_\r\n"),
+            Reply::Boolean(b) => write_line(buf, b'#', if *b { b"t" } else { b"f" }),
+            Reply::Double(s) => write_line(buf, b',', s.as_bytes()),
+            Reply::BigNumber(s) => write_line(buf, b'(', s.as_bytes()),
+            Reply::BulkError(s) => write_bulk(buf, b'!', s),
+            Reply::VerbatimString { format, text } => {
+                write_line(buf, b'=', (text.len() + 4).to_string().as_bytes());
+                buf.extend_from_slice(format);
+                buf.push(b':');
+                buf.extend_from_slice(text);
+                buf.extend_from_slice(b"\r\n");
+            }
+            Reply::Map(pairs) => write_pairs(buf, b'%', pairs),
+            Reply::Set(arr) => write_aggregate(buf, b'~', arr),
+            Reply::Attribute { attributes, reply } => {
+                write_pairs(buf, b'|', attributes);
+                reply.write(buf);
+            }
+            Reply::Push(arr) => write_aggregate(buf, b'>', arr),
+        }
+    }
+
+    // The reply `value` was read from, which is how `from_value` reads a `Reply`.
+    // The three nulls stay apart, an unsigned integer must fit in an `i64`.
+    pub(crate) fn from_value(value: RESPType) -> Result<Reply> {
+        let reply = match value {
+            RESPType::SimpleString(s) => Reply::SimpleString(s.into_bytes()),
+            RESPType::Error(s) => Reply::Error(s.into_bytes()),
+            RESPType::Integer(n) => Reply::Integer(n),
+            RESPType::UInteger(n) => Reply::Integer(i64::try_from(n).map_err(|_| Error::IntegerOverflow { pos: 0 })?),
+            RESPType::Double(v) => Reply::Double(match v {
+                _ if v.is_nan() => "nan".to_owned(),
+                f64::INFINITY => "inf".to_owned(),
+                f64::NEG_INFINITY => "-inf".to_owned(),
+                _ => v.to_string(),
+            }),
+            RESPType::Boolean(b) => Reply::Boolean(b),
+            RESPType::BulkString(s) => Reply::BulkString(s),
+            RESPType::BigNumber(digits) => Reply::BigNumber(digits),
+            RESPType::Array(arr) => Reply::Array(arr.into_iter().map(Reply::from_value).collect::<Result<_>>()?),
+            RESPType::Map(pairs) => Reply::Map(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| Ok((Reply::from_value(k)?, Reply::from_value(v)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            RESPType::None => Reply::NullBulk,
+            RESPType::NullArray => Reply::NullArray,
+            RESPType::Null => Reply::Null,
+        };
+        Ok(reply)
+    }
+
+    // Parse the frame at the start of {input}, whose end has already been found,
+    // returning it with the number of bytes it spans.
+    pub(crate) fn parse(input: &[u8]) -> Result<(Reply, usize)> {
+        let mut parser = ReplyParser { input, pos: 0 };
+        let reply = parser.parse()?;
+        Ok((reply, parser.pos))
+    }
+}
+
+fn write_line(buf: &mut Vec<u8>, prefix: u8, content: &[u8]) {
+    buf.push(prefix);
+    buf.extend_from_slice(content);
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn write_bulk(buf: &mut Vec<u8>, prefix: u8, content: &[u8]) {
+    write_line(buf, prefix, content.len().to_string().as_bytes());
+    buf.extend_from_slice(content);
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn write_aggregate(buf: &mut Vec<u8>, prefix: u8, elements: &[Reply]) {
+    write_line(buf, prefix, elements.len().to_string().as_bytes());
+    for element in elements {
+        element.write(buf);
+    }
+}
+
+fn write_pairs(buf: &mut Vec<u8>, prefix: u8, pairs: &[(Reply, Reply)]) {
+    write_line(buf, prefix, pairs.len().to_string().as_bytes());
+    for (key, value) in pairs {
+        key.write(buf);
+        value.write(buf);
+    }
+}
+
+struct ReplyParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ReplyParser<'a> {
+    fn parse(&mut self) -> Result<Reply> {
+        let start = self.pos;
+        let prefix = *self.input.get(self.pos).ok_or(Error::Eof)?;
+        self.pos += 1;
+        let reply = match prefix {
+            b'+' => Reply::SimpleString(self.line()?.to_vec()),
+            b'-' => Reply::Error(self.line()?.to_vec()),
+            b':' => Reply::Integer(self.number()?),
+            b'$' => match self.bulk()? {
+                Some(s) => Reply::BulkString(s.to_vec()),
+                None => Reply::NullBulk,
+            },
+            b'*' => match self.length()? {
+                Some(len) => Reply::Array(self.elements(len)?),
+                None => Reply::NullArray,
+            },
+            b'_' => {
+                self.line()?;
+                Reply::Null
+            }
+            b'#' => match self.line()? {
+                b"t" => Reply::Boolean(true),
+                b"f" => Reply::Boolean(false),
+                other => return Err(de::Error::invalid_value(Unexpected::Bytes(other), &"t or f")),
+            },
+            b',' => Reply::Double(self.text()?),
+            b'(' => Reply::BigNumber(self.text()?),
+            b'!' => Reply::BulkError(self.bulk()?.ok_or(Error::Syntax(start))?.to_vec()),
+            b'=' => match self.bulk()?.ok_or(Error::Syntax(start))? {
+                [a, b, c, b':', text @ ..] => Reply::VerbatimString { format: [*a, *b, *c], text: text.to_vec() },
+                other => return Err(de::Error::invalid_value(Unexpected::Bytes(other), &"a format and a text")),
+            },
+            b'%' => {
+                let len = self.count(start)?;
+                Reply::Map(self.pairs(len)?)
+            }
+            b'~' => {
+                let len = self.count(start)?;
+                Reply::Set(self.elements(len)?)
+            }
+            b'|' => {
+                let len = self.count(start)?;
+                let attributes = self.pairs(len)?;
+                Reply::Attribute { attributes, reply: Box::new(self.parse()?) }
+            }
+            b'>' => {
+                let len = self.count(start)?;
+                Reply::Push(self.elements(len)?)
+            }
+            _ => return Err(Error::ExpectedSign(start)),
+        };
+        Ok(reply)
+    }
+
+    // The rest of the line, consuming its "\r\n".
+    fn line(&mut self) -> Result<&'a [u8]> {
+        let rest = &self.input[self.pos..];
+        let len = rest.iter().position(|&b| b == b'\r').ok_or(Error::Eof)?;
+        if rest.get(len + 1) != Some(&b'\n') {
+            return Err(Error::UnexpectedCR(self.pos + len));
+        }
+        self.pos += len + 2;
+        Ok(&rest[..len])
+    }
+
+    fn text(&mut self) -> Result<String> {
+        let pos = self.pos;
+        let line = self.line()?;
+        String::from_utf8(line.to_vec()).map_err(|_| Error::Syntax(pos))
+    }
+
+    fn number(&mut self) -> Result<i64> {
+        let pos = self.pos;
+        String::from_utf8_lossy(self.line()?)
+            .parse()
+            .map_err(|err| Error::ParseIntError { err, pos })
+    }
+
+    // A length, None for -1.
+    fn length(&mut self) -> Result<Option<usize>> {
+        let pos = self.pos;
+        match self.number()? {
+            -1 => Ok(None),
+            len => usize::try_from(len).map(Some).map_err(|_| Error::Syntax(pos)),
+        }
+    }
+
+    // The length of an aggregate starting at {start} that has no null form.
+    fn count(&mut self, start: usize) -> Result<usize> {
+        self.length()?.ok_or(Error::Syntax(start))
+    }
+
+    fn bulk(&mut self) -> Result<Option<&'a [u8]>> {
+        let Some(len) = self.length()? else {
+            return Ok(None);
+        };
+        let end = self.pos + len;
+        if self.input.len() < end + 2 {
+            return Err(Error::Eof);
+        }
+        if &self.input[end..end + 2] != b"\r\n" {
+            return Err(Error::Syntax(end));
+        }
+        let content = &self.input[self.pos..end];
+        self.pos = end + 2;
+        Ok(Some(content))
+    }
+
+    fn elements(&mut self, len: usize) -> Result<Vec<Reply>> {
+        // every element takes 3 bytes at least
+        let mut elements = Vec::with_capacity(len.min(self.input.len() - self.pos));
+        for _ in 0..len {
+            elements.push(self.parse()?);
+        }
+        Ok(elements)
+    }
+
+    fn pairs(&mut self, len: usize) -> Result<Vec<(Reply, Reply)>> {
+        let mut pairs = Vec::with_capacity(len.min(self.input.len() - self.pos));
+        for _ in 0..len {
+            pairs.push((self.parse()?, self.parse()?));
+        }
+        Ok(pairs)
+    }
+}
+
+impl Serialize for Reply {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(REPLY_TOKEN, &EncodedFrame(&self.to_bytes()))
+    }
+}
+
+struct EncodedFrame<'a>(&'a [u8]);
+
+impl Serialize for EncodedFrame<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Reply {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(REPLY_TOKEN, ReplyVisitor)
+    }
+}
+
+struct ReplyVisitor;
+
+impl<'de> Visitor<'de> for ReplyVisitor {
+    type Value = Reply;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("an encoded frame")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Self::Value, E> {
+        match Reply::parse(v) {
+            Ok((reply, len)) if len == v.len() => Ok(reply),
+            Ok((_, len)) => Err(E::custom(Error::TrailingCharacters(len))),
+            Err(err) => Err(E::custom(err)),
+        }
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+#[cfg(test)]
+mod reply_test {
+    use crate::error::ErrorKind;
+    use crate::{from_slice, from_slice_with, from_value, to_value, to_vec, DeserializerBuilder, Limits, RESPType, Reply, Result};
+
+    #[test]
+    fn test_nulls() -> Result<()> {
+        let replies: Vec<Reply> = from_slice(b"*3\r\n$-1\r\n*-1\r\n_\r\n")?;
+        assert_eq!(replies, [Reply::NullBulk, Reply::NullArray, Reply::Null]);
+        assert_eq!(to_vec(&replies)?, b"*3\r\n$-1\r\n*-1\r\n_\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_variants() -> Result<()> {
+        let frame = b"*4\r\n#f\r\n=7\r\nmkd:# a\r\n|1\r\n+a\r\n:1\r\n~1\r\n,2.0\r\n!3\r\nERR\r\n";
+        let reply: Reply = from_slice(frame)?;
+        assert_eq!(
+            reply,
+            Reply::Array(vec![
+                Reply::Boolean(false),
+                Reply::VerbatimString { format: *b"mkd", text: b"# a".to_vec() },
+                Reply::Attribute {
+                    attributes: vec![(Reply::SimpleString(b"a".to_vec()), Reply::Integer(1))],
+                    reply: Box::new(Reply::Set(vec![Reply::Double("2.0".to_owned())])),
+                },
+                Reply::BulkError(b"ERR".to_vec()),
+            ])
+        );
+        assert_eq!(reply.to_bytes(), frame);
+        Ok(())
+    }
+
+    #[test]
+    fn test_value() -> Result<()> {
+        let frames: [&[u8]; 10] = [
+            b"_\r\n", b"$-1\r\n", b"*-1\r\n", b"#t\r\n", b":-3\r\n", b"+OK\r\n", b"$2\r\nhi\r\n",
+            b",1.5\r\n", b"(12345678901234567890\r\n", b"*2\r\n:1\r\n%1\r\n+a\r\n_\r\n",
+        ];
+        for frame in frames {
+            let reply: Reply = from_slice(frame)?;
+            let value = to_value(&reply)?;
+            assert_eq!(value, from_slice::<RESPType>(frame)?);
+            assert_eq!(from_value::<Reply>(value)?, reply);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() -> Result<()> {
+        assert!(from_slice::<Reply>(b"#x\r\n").is_err_and(|err| err.kind() == ErrorKind::Message));
+        assert!(from_slice::<Reply>(b"=3\r\ntxt\r\n").is_err_and(|err| err.kind() == ErrorKind::Message));
+        assert!(from_slice::<Reply>(b"~2\r\n:1\r\n").is_err_and(|err| err.kind() == ErrorKind::Eof));
+        assert!(from_slice::<Reply>(b"?1\r\n").is_err_and(|err| err.kind() == ErrorKind::ExpectedSign));
+        let mut limits = Limits::default();
+        limits.set_max_elements(2);
+        let config = DeserializerBuilder::new().with_limits(limits);
+        assert!(
+            from_slice_with::<Reply>(b"~2\r\n:1\r\n:2\r\n", &config)
+                .is_err_and(|err| err.kind() == ErrorKind::TooManyElements)
+        );
+        Ok(())
+    }
+}
@@ -1,49 +1,692 @@
 use crate::error::{Error, Result};
-use crate::RESPType;
-use serde::ser::{Impossible, SerializeSeq};
+use crate::pair::PAIR_TOKEN;
+use crate::reply::REPLY_TOKEN;
+use crate::{Limits, RESPType, RespVersion};
+use serde::ser::{SerializeMap, SerializeSeq};
 use serde::{ser, Serialize};
-use std::io::Write;
+use std::io::{self, Read, Write};
 
+// Newtype names `RESPType` uses to tell this serializer which kind of string it
+// is writing. Any other serializer just sees a newtype around a plain string.
+pub(crate) const SIMPLE_STRING_TOKEN: &str = "$serde_resp::SimpleString";
+pub(crate) const ERROR_TOKEN: &str = "$serde_resp::Error";
+pub(crate) const NULL_BULK_TOKEN: &str = "$serde_resp::NullBulk";
+pub(crate) const NULL_ARRAY_TOKEN: &str = "$serde_resp::NullArray";
+pub(crate) const NULL_TOKEN: &str = "$serde_resp::Null";
+pub(crate) const BIG_NUMBER_TOKEN: &str = "$serde_resp::BigNumber";
+// Newtype struct wrapping the digits of an integer above `i64::MAX`.
+pub(crate) const UINTEGER_TOKEN: &str = "$serde_resp::UInteger";
+
+/// Serializer writing RESP data into a `Write`.
+///
+/// Values are written as they are serialized, so an error may leave a partially
+/// written frame behind. See [`to_writer`] for the state of the stream then.
 pub struct Serializer<W: Write> {
     buffer: itoa::Buffer,
     writer: W,
+    // Set by a marker newtype, consumed by the next `serialize_str`.
+    string_prefix: Option<u8>,
+    // Set by `Reply`, the next `serialize_bytes` writes an encoded frame as is.
+    encoded_frame: bool,
+    config: SerializerBuilder,
+    stats: SerStats,
+    // Number of arrays being written that the current value is nested in.
+    depth: usize,
+    // Length of the sequence whose header waits for its first element, as
+    // it counts both the key and value of `Pair` elements.
+    pending_seq: Option<usize>,
+    // Depths of the sequences of pairs being written.
+    pair_seqs: Vec<usize>,
+    // Encoded keys and values of the maps being written with sorted keys.
+    sorted_maps: Vec<Vec<(Vec<u8>, Vec<u8>)>>,
+    // Sequences of unknown length being written, which everything written
+    // goes to until their end.
+    unsized_seqs: Vec<UnsizedSeq>,
+}
+
+// A sequence of unknown length, whose header waits for its end.
+struct UnsizedSeq {
+    // Depth of its elements.
+    depth: usize,
+    // Number of elements, counting both the key and value of `Pair` elements.
+    len: usize,
+    // Encoded elements.
+    elements: Vec<u8>,
+}
+
+/// Output options of a [`Serializer`], reusable across values.
+///
+/// The defaults are those of `Serializer::new`, which writes RESP2.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializerBuilder {
+    limits: Limits,
+    version: RespVersion,
+    null_style: Option<NullStyle>,
+    prefer_simple_strings: bool,
+    on_invalid_simple_string: InvalidSimpleStringPolicy,
+    sort_map_keys: bool,
+}
+
+/// How a null is written.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NullStyle {
+    /// `$-1\r\n`
+    NullBulk,
+    /// `*-1\r\n`
+    NullArray,
+    /// `_\r\n`, only valid in RESP3.
+    Resp3Null,
+}
+
+/// What to do with a simple string or error containing `\r` or `\n`,
+/// which can't be written as is.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum InvalidSimpleStringPolicy {
+    /// Fail with `Error::InvalidSimpleString`.
+    #[default]
+    Error,
+    /// Write the string with every `\r` and `\n` replaced by a space.
+    ReplaceWithSpace,
+    /// Write the string as a bulk string instead. An error then reaches
+    /// the peer as a plain string.
+    EncodeAsBulk,
+}
+
+impl SerializerBuilder {
+    pub fn new() -> Self {
+        SerializerBuilder::default()
+    }
+
+    /// Replace all size limits at once.
+    ///
+    /// The frame size and element limits only apply to deserialization.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_max_bulk_len(mut self, max_bulk_len: usize) -> Self {
+        self.limits.max_bulk_len = max_bulk_len;
+        self
+    }
+
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.limits.max_array_len = max_array_len;
+        self
+    }
+
+    /// Set the protocol version to write. Default is RESP2.
+    ///
+    /// Nulls are written as `_\r\n` in RESP3, unless a null style is set.
+    pub fn version(mut self, version: RespVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set how `None` and unit are written, overriding the version default.
+    ///
+    /// The nulls of `RESPType` keep their own encoding, so that a frame read
+    /// into a `RESPType` is written back as it was received.
+    pub fn null_style(mut self, null_style: NullStyle) -> Self {
+        self.null_style = Some(null_style);
+        self
+    }
+
+    /// Write plain strings as simple strings when they contain no `\r` or `\n`,
+    /// and as bulk strings otherwise. Off by default.
+    ///
+    /// The output is valid either way, but the encoding of a string no longer
+    /// follows its source: a bulk string read into a `String` may be written
+    /// back as a simple string. `RESPType` strings keep their own encoding.
+    pub fn prefer_simple_strings(mut self, enabled: bool) -> Self {
+        self.prefer_simple_strings = enabled;
+        self
+    }
+
+    /// Set what to do with simple strings and errors containing `\r` or `\n`.
+    pub fn on_invalid_simple_string(mut self, policy: InvalidSimpleStringPolicy) -> Self {
+        self.on_invalid_simple_string = policy;
+        self
+    }
+
+    /// Write the entries of maps ordered by the bytes of their encoded keys,
+    /// so that maps with no order of their own, such as `HashMap`, are always
+    /// written the same. Off by default.
+    ///
+    /// Each entry is buffered until the whole map is serialized. Structs keep
+    /// the order of their fields.
+    pub fn sort_map_keys(mut self, enabled: bool) -> Self {
+        self.sort_map_keys = enabled;
+        self
+    }
+
+    /// Set how deep arrays may be nested, a top-level array being at depth 1.
+    /// Default is 128.
+    ///
+    /// A value nesting deeper fails with `Error::DepthLimitExceeded` before
+    /// the offending array header is written.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.limits.max_depth = max_depth;
+        self
+    }
+
+    /// Set how many bytes a serializer may write in total. No limit by default.
+    ///
+    /// A write that would go past the limit fails with `Error::OutputTooLarge`
+    /// before any of it reaches the writer, so the output never exceeds the
+    /// limit, though the value being written is left truncated.
+    pub fn max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.limits.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    /// Create a serializer writing into `writer` with these options.
+    pub fn build<W: Write>(&self, writer: W) -> Serializer<W> {
+        Serializer::with_options(writer, self.clone())
+    }
+
+    fn null(&self) -> &'static [u8] {
+        let style = match (self.null_style, self.version) {
+            (Some(style), _) => style,
+            (None, RespVersion::Resp2) => NullStyle::NullBulk,
+            (None, RespVersion::Resp3) => NullStyle::Resp3Null,
+        };
+        match style {
+            NullStyle::NullBulk => b"$-1\r\n",
+            NullStyle::NullArray => b"*-1\r\n",
+            NullStyle::Resp3Null => b"_\r\n",
+        }
+    }
+}
+
+/// Counters of what a [`Serializer`] has written so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerStats {
+    /// Top-level values started.
+    pub frames: usize,
+    /// Bytes handed to the writer.
+    pub bytes: usize,
+}
+
+impl<W: Write> Serializer<W> {
+    pub fn new(writer: W) -> Self {
+        Serializer::with_options(writer, SerializerBuilder::default())
+    }
+
+    pub fn with_options(writer: W, config: SerializerBuilder) -> Self {
+        Serializer {
+            buffer: itoa::Buffer::new(),
+            writer,
+            string_prefix: None,
+            encoded_frame: false,
+            config,
+            stats: SerStats::default(),
+            depth: 0,
+            pending_seq: None,
+            pair_seqs: Vec::new(),
+            sorted_maps: Vec::new(),
+            unsized_seqs: Vec::new(),
+        }
+    }
+
+    /// Number of top-level values and bytes written so far.
+    pub fn stats(&self) -> SerStats {
+        self.stats
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// Serializing never flushes, so call this once done writing when the
+    /// writer is buffered.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(|err| Error::IoError { err, written: self.stats.bytes })
+    }
+
+    /// Open a RESP3 streamed array, `*?\r\n`, whose elements are written one at a time.
+    ///
+    /// The array must be closed with [`StreamingArray::finish`], otherwise the
+    /// peer is left waiting for more elements.
+    pub fn begin_streamed_array(&mut self) -> Result<StreamingArray<'_, W>> {
+        self.check_depth()?;
+        self.start_value();
+        self.write_all(b"*?\r\n")?;
+        self.depth += 1;
+        Ok(StreamingArray { ser: self })
+    }
+
+    /// Replace all size limits at once.
+    ///
+    /// The frame size and element limits only apply to deserialization.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.config.limits = limits;
+        self
+    }
+
+    /// Set the maximum payload length of a bulk string. Default is 512MB, the protocol max.
+    ///
+    /// Longer payloads are rejected with `Error::BulkStringOverflow` before anything is written.
+    pub fn with_max_bulk_len(mut self, max_bulk_len: usize) -> Self {
+        self.config.limits.max_bulk_len = max_bulk_len;
+        self
+    }
+
+    /// Set the maximum number of elements an array may have. Default is 1M.
+    ///
+    /// Longer arrays are rejected with `Error::ArrayTooLarge` before their header is written.
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.config.limits.max_array_len = max_array_len;
+        self
+    }
+
+    /// Write map entries ordered by key, see [`SerializerBuilder::sort_map_keys`].
+    pub fn sort_map_keys(mut self, enabled: bool) -> Self {
+        self.config.sort_map_keys = enabled;
+        self
+    }
 }
 
 pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+    Ok(String::from_utf8(to_vec(value)?)?)
+}
+
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    to_vec_with(value, &SerializerBuilder::default())
+}
+
+/// Encode an already parsed value for a peer speaking `target`, as a proxy
+/// between RESP2 and RESP3 connections would.
+///
+/// Going to RESP2, maps become flat arrays of keys and values, RESP3 nulls
+/// null bulk strings and big numbers bulk strings. Going to RESP3, null bulk
+/// strings and null arrays become `_`. Arrays are kept as they are, a flat
+/// array read from RESP2 can't be told apart from a map.
+pub fn transcode(value: &RESPType, target: RespVersion) -> Result<Vec<u8>> {
+    let config = SerializerBuilder::new().version(target);
+    match target {
+        RespVersion::Resp2 => to_vec_with(&with_resp2_nulls(value), &config),
+        RespVersion::Resp3 => to_vec_with(value, &config),
+    }
+}
+
+// Replace the RESP3 nulls in {value}, which are written as is, by null bulk strings.
+fn with_resp2_nulls(value: &RESPType) -> RESPType {
+    match value {
+        RESPType::Null => RESPType::None,
+        RESPType::Array(arr) => RESPType::Array(arr.iter().map(with_resp2_nulls).collect()),
+        RESPType::Map(pairs) => {
+            RESPType::Map(pairs.iter().map(|(k, v)| (with_resp2_nulls(k), with_resp2_nulls(v))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Like `to_vec`, with the output options of `config`.
+pub fn to_vec_with<T: Serialize>(value: &T, config: &SerializerBuilder) -> Result<Vec<u8>> {
     let mut buf: Vec<u8> = Vec::new();
-    to_writer(value, &mut buf)?;
-    Ok(String::from_utf8(buf)?)
+    to_writer_with(value, &mut buf, config)?;
+    Ok(buf)
+}
+
+/// Write the nil reply of the given protocol version, `$-1\r\n` or `_\r\n`,
+/// whatever a serializer would default to.
+pub fn write_null<W: Write>(writer: &mut W, version: RespVersion) -> Result<()> {
+    to_writer(&RESPType::null(version), writer)
+}
+
+/// Write the command `args`, an array of bulk strings, into `writer`.
+///
+/// This is what serializing `args` writes, straight from the bytes of each
+/// argument without any allocation.
+pub fn encode_command_str<W: Write>(args: &[&str], writer: &mut W) -> Result<()> {
+    encode_command_str_with(args, writer, &SerializerBuilder::default())
+}
+
+/// Like `encode_command_str`, with the limits of `config`.
+///
+/// The whole command is checked against them before anything is written.
+pub fn encode_command_str_with<W: Write>(args: &[&str], writer: &mut W, config: &SerializerBuilder) -> Result<()> {
+    let limits = &config.limits;
+    if args.len() > limits.max_array_len {
+        return Err(Error::ArrayTooLarge { declared: args.len(), limit: limits.max_array_len });
+    }
+    if args.iter().any(|arg| arg.len() > limits.max_bulk_len) {
+        return Err(Error::BulkStringOverflow);
+    }
+    // a header is its prefix, its digits and "\r\n"
+    let header = |len: usize| itoa::Buffer::new().format(len).len() + 3;
+    let size = args.iter().fold(header(args.len()), |size, arg| size.saturating_add(header(arg.len()) + arg.len() + 2));
+    if size > limits.max_output_bytes {
+        return Err(Error::OutputTooLarge { limit: limits.max_output_bytes });
+    }
+    let mut serializer = config.build(writer);
+    serializer.start_value();
+    serializer.write_header(b'*', args.len())?;
+    for arg in args {
+        serializer.write_header(b'$', arg.len())?;
+        serializer.write_all(arg.as_bytes())?;
+        serializer.write_all(b"\r\n")?;
+    }
+    Ok(())
+}
+
+/// Write a bulk string of `len` bytes copied from `src` into `writer`, so a
+/// large payload is forwarded through a fixed size buffer rather than read
+/// into memory first.
+///
+/// Fails with `Error::Eof` if `src` ends before `len` bytes, after writing
+/// part of the payload. Read errors are returned as `Error::IoError` like
+/// write errors, carrying the number of bytes written.
+pub fn write_bulk_from_reader<W: Write, R: Read>(writer: &mut W, len: usize, src: &mut R) -> Result<()> {
+    write_bulk_from_reader_with(writer, len, src, &SerializerBuilder::default())
 }
 
+/// Like `write_bulk_from_reader`, with the limits of `config`, which are
+/// checked against `len` before anything is read or written.
+pub fn write_bulk_from_reader_with<W: Write, R: Read>(
+    writer: &mut W,
+    len: usize,
+    src: &mut R,
+    config: &SerializerBuilder,
+) -> Result<()> {
+    let limits = &config.limits;
+    if len > limits.max_bulk_len {
+        return Err(Error::BulkStringOverflow);
+    }
+    let size = (itoa::Buffer::new().format(len).len() + 3).saturating_add(len).saturating_add(2);
+    if size > limits.max_output_bytes {
+        return Err(Error::OutputTooLarge { limit: limits.max_output_bytes });
+    }
+    let mut serializer = config.build(writer);
+    serializer.start_value();
+    serializer.write_header(b'$', len)?;
+    let mut buf = [0; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(buf.len());
+        match src.read(&mut buf[..chunk]) {
+            Ok(0) => return Err(Error::Eof),
+            Ok(n) => {
+                serializer.write_all(&buf[..n])?;
+                remaining -= n;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(Error::IoError { err, written: serializer.stats.bytes }),
+        }
+    }
+    serializer.write_all(b"\r\n")
+}
+
+/// Serialize `value` into `writer`.
+///
+/// Short writes and `Interrupted` errors are retried. Any other write error,
+/// including `WouldBlock`, is returned as `Error::IoError` carrying the number
+/// of bytes the writer had accepted. In that case the peer has received a
+/// truncated frame and the stream is out of sync, so connection handling code
+/// should close it, or resend the remainder of a frame it serialized with `to_vec`.
+///
+/// The writer is not flushed, so that several values can be written before a
+/// single flush. Use [`to_writer_pipeline`] to write many values and flush once.
 pub fn to_writer<T, W>(value: &T, writer: &mut W) -> Result<()>
 where
     T: Serialize,
     W: Write,
 {
-    let mut serializer = Serializer {
-        buffer: itoa::Buffer::new(),
-        writer,
-    };
+    to_writer_with(value, writer, &SerializerBuilder::default())
+}
+
+/// Like `to_writer`, with the output options of `config`.
+pub fn to_writer_with<T, W>(value: &T, writer: &mut W, config: &SerializerBuilder) -> Result<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    let mut serializer = config.build(writer);
     value.serialize(&mut serializer)?;
     Ok(())
 }
 
-impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+/// Serialize every value of `values` into `writer` back to back, then flush it once.
+///
+/// This is how a client sends a pipeline of commands. Errors are reported as
+/// by [`to_writer`], and the values after a failing one are not written.
+pub fn to_writer_pipeline<'a, T, I, W>(values: I, writer: &mut W) -> Result<()>
+where
+    T: 'a + ?Sized + Serialize,
+    I: IntoIterator<Item = &'a T>,
+    W: Write,
+{
+    to_writer_pipeline_with(values, writer, &SerializerBuilder::default())
+}
+
+/// Like `to_writer_pipeline`, with the output options of `config`. The
+/// `max_output_bytes` limit applies to the pipeline as a whole.
+pub fn to_writer_pipeline_with<'a, T, I, W>(values: I, writer: &mut W, config: &SerializerBuilder) -> Result<()>
+where
+    T: 'a + ?Sized + Serialize,
+    I: IntoIterator<Item = &'a T>,
+    W: Write,
+{
+    let mut serializer = config.build(writer);
+    for value in values {
+        value.serialize(&mut serializer)?;
+    }
+    serializer.flush()
+}
+
+impl<W: Write> Serializer<W> {
+    // Like `Write::write_all`, but keeps count of the bytes accepted by the writer
+    // so a failure can report how much of the frame already went out.
+    // Inside a sequence of unknown length, the bytes are buffered instead.
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+        self.write_pending_header()?;
+        let limit = self.config.limits.max_output_bytes;
+        let buffered: usize = self.unsized_seqs.iter().map(|seq| seq.elements.len()).sum();
+        if self.stats.bytes.saturating_add(buffered).saturating_add(buf.len()) > limit {
+            return Err(Error::OutputTooLarge { limit });
+        }
+        if let Some(seq) = self.unsized_seqs.last_mut() {
+            seq.elements.extend_from_slice(buf);
+            return Ok(());
+        }
+        while !buf.is_empty() {
+            match self.writer.write(buf) {
+                Ok(0) => {
+                    let err = io::Error::new(io::ErrorKind::WriteZero, "failed to write whole frame");
+                    return Err(Error::IoError { err, written: self.stats.bytes });
+                }
+                Ok(n) => {
+                    self.stats.bytes += n;
+                    buf = &buf[n..];
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::IoError { err, written: self.stats.bytes }),
+            }
+        }
+        Ok(())
+    }
+
+    // Write a simple string or an error, {v} must not contain "\r" or "\n".
+    fn write_line(&mut self, prefix: u8, v: &str) -> Result<()> {
+        self.start_value();
+        self.write_all(&[prefix])?;
+        self.write_all(v.as_bytes())?;
+        self.write_all(b"\r\n")
+    }
+
+    // Write the header line of an aggregate or bulk string of length {len}.
+    fn write_header(&mut self, prefix: u8, len: usize) -> Result<()> {
+        let mut buffer = itoa::Buffer::new();
+        self.write_all(&[prefix])?;
+        self.write_all(buffer.format(len).as_bytes())?;
+        self.write_all(b"\r\n")
+    }
+
+    // Open a map of {pairs} keys and values.
+    fn open_map(&mut self, pairs: usize) -> Result<&mut Self> {
+        if self.config.version == RespVersion::Resp2 {
+            return self.open_array(Some(pairs.saturating_mul(2)), false);
+        }
+        self.check_depth()?;
+        let declared = pairs.saturating_mul(2);
+        if declared > self.config.limits.max_array_len {
+            return Err(Error::ArrayTooLarge { declared, limit: self.config.limits.max_array_len });
+        }
+        self.start_value();
+        self.write_all(format!("%{pairs}\r\n").as_bytes())?;
+        self.depth += 1;
+        Ok(self)
+    }
+
+    // Open an array of {len} elements, holding its header back until the first
+    // element if {deferred}, or until its end if the length is unknown.
+    fn open_array(&mut self, len: Option<usize>, deferred: bool) -> Result<&mut Self> {
+        self.check_depth()?;
+        match len {
+            Some(x) if x > self.config.limits.max_array_len => {
+                return Err(Error::ArrayTooLarge { declared: x, limit: self.config.limits.max_array_len });
+            }
+            Some(x) if deferred => {
+                self.start_value();
+                self.write_pending_header()?;
+                self.pending_seq = Some(x);
+            }
+            Some(x) => {
+                self.start_value();
+                self.write_all(format!("*{x}\r\n").as_bytes())?;
+            }
+            None => {
+                self.start_value();
+                self.write_pending_header()?;
+                self.unsized_seqs.push(UnsizedSeq { depth: self.depth + 1, len: 0, elements: Vec::new() });
+            }
+        }
+        self.depth += 1;
+        Ok(self)
+    }
+
+    // Write the header of a sequence with no element written yet.
+    fn write_pending_header(&mut self) -> Result<()> {
+        match self.pending_seq.take() {
+            Some(len) => self.write_header(b'*', len),
+            None => Ok(()),
+        }
+    }
+
+    // Start writing a `Pair` as two elements of the sequence it is in, the
+    // first pair writing the header of the sequence, unless its length is unknown.
+    fn open_pair(&mut self) -> Result<&mut Self> {
+        if let Some(len) = self.pending_seq.take() {
+            let declared = len.saturating_mul(2);
+            if declared > self.config.limits.max_array_len {
+                return Err(Error::ArrayTooLarge { declared, limit: self.config.limits.max_array_len });
+            }
+            self.write_header(b'*', declared)?;
+            self.pair_seqs.push(self.depth);
+        } else if self.pair_seqs.last() != Some(&self.depth) {
+            if !self.unsized_seqs.last().is_some_and(|seq| seq.depth == self.depth && seq.len == 0) {
+                return Err(Error::Unsupported { what: "Pair outside of a sequence of pairs" });
+            }
+            self.pair_seqs.push(self.depth);
+        }
+        Ok(self)
+    }
+
+    // Write the digits of an integer too wide for `:`.
+    fn write_wide_int(&mut self, digits: &str) -> Result<()> {
+        match self.config.version {
+            RespVersion::Resp2 => ser::Serializer::serialize_bytes(self, digits.as_bytes()),
+            RespVersion::Resp3 => self.write_line(b'(', digits),
+        }
+    }
+
+    // Write a double, {text} being the shortest decimal form of {v}. RESP2 has
+    // no doubles, they are written as a bulk string of that text as Redis does.
+    fn write_double(&mut self, v: f64, text: &str) -> Result<()> {
+        let text = match v {
+            _ if v.is_nan() => "nan",
+            f64::INFINITY => "inf",
+            f64::NEG_INFINITY => "-inf",
+            _ => text,
+        };
+        match self.config.version {
+            RespVersion::Resp2 => ser::Serializer::serialize_bytes(self, text.as_bytes()),
+            RespVersion::Resp3 => self.write_line(b',', text),
+        }
+    }
+
+    // Check that an array can be opened at the current depth.
+    fn check_depth(&self) -> Result<()> {
+        let limit = self.config.limits.max_depth;
+        if self.depth >= limit {
+            return Err(Error::DepthLimitExceeded { limit });
+        }
+        Ok(())
+    }
+
+    // Called by every method that begins writing a value of its own.
+    fn start_value(&mut self) {
+        if self.depth == 0 {
+            self.stats.frames += 1;
+        }
+    }
+
+    // Serialize a key or value of a map with sorted keys into a buffer at the
+    // depth of its container.
+    fn encode_entry<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut ser = Serializer::with_options(Vec::new(), self.config.clone());
+        ser.depth = self.depth;
+        value.serialize(&mut ser)?;
+        Ok(ser.writer)
+    }
+}
+
+/// Handle to a streamed array opened by [`Serializer::begin_streamed_array`].
+pub struct StreamingArray<'a, W: Write> {
+    ser: &'a mut Serializer<W>,
+}
+
+impl<W: Write> StreamingArray<'_, W> {
+    /// Write the next element.
+    pub fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.ser)
+    }
+
+    /// Close the array with the `.\r\n` terminator.
+    pub fn finish(self) -> Result<()> {
+        self.ser.depth -= 1;
+        self.ser.write_all(b".\r\n")
+    }
+}
+
+fn has_crlf(v: &str) -> bool {
+    v.bytes().any(|b| b == b'\r' || b == b'\n')
+}
+
+impl<W: Write> ser::Serializer for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Impossible<(), Error>;
-    type SerializeMap = Impossible<(), Error>;
-    type SerializeStruct = Impossible<(), Error>;
-    type SerializeStructVariant = Impossible<(), Error>;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
 
+    // RESP2 has no booleans, they are written as the integers 0 and 1 then.
     fn serialize_bool(self, v: bool) -> Result<()> {
-        if v {
-            self.serialize_i64(1i64)
-        } else {
-            self.serialize_i64(0i64)
+        match self.config.version {
+            RespVersion::Resp2 => self.serialize_i64(i64::from(v)),
+            RespVersion::Resp3 => {
+                self.start_value();
+                self.write_all(if v { b"#t\r\n" } else { b"#f\r\n" })
+            }
         }
     }
 
@@ -60,8 +703,9 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
+        self.start_value();
         let content = format!(":{}\r\n", self.buffer.format(v));
-        self.writer.write_all(content.as_bytes())?;
+        self.write_all(content.as_bytes())?;
         Ok(())
     }
 
@@ -77,45 +721,88 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_u64(self, _: u64) -> Result<()> {
-        unimplemented!()
+    // RESP integers are signed 64 bit.
+    // Values above `i64::MAX` are written as integers all the same, as
+    // `RESPType::UInteger` is, for readers with wide integers.
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.write_line(b':', itoa::Buffer::new().format(v)),
+        }
+    }
+
+    // Values beyond the range of RESP integers are written as a big number in
+    // RESP3, and as a bulk string of their digits in RESP2.
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.write_wide_int(&v.to_string()),
+        }
     }
 
-    fn serialize_f32(self, _: f32) -> Result<()> {
-        unimplemented!()
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.write_wide_int(&v.to_string()),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.write_double(f64::from(v), &v.to_string())
     }
 
-    fn serialize_f64(self, _: f64) -> Result<()> {
-        unimplemented!()
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.write_double(v, &v.to_string())
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
-        self.writer.write(&[v as u8])?;
-        Ok(())
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
+    // Plain strings are written as bulk strings, simple strings and errors
+    // are only written when requested by `RESPType`.
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.writer.write_all(v.as_bytes())?;
-        self.writer.write_all(b"\r\n")?;
-        Ok(())
+        match self.string_prefix.take() {
+            // a big number in RESP2, which has none
+            Some(b'$') => self.serialize_bytes(v.as_bytes()),
+            Some(prefix) if has_crlf(v) => match self.config.on_invalid_simple_string {
+                InvalidSimpleStringPolicy::Error => Err(Error::InvalidSimpleString),
+                InvalidSimpleStringPolicy::ReplaceWithSpace => {
+                    self.write_line(prefix, &v.replace(['\r', '\n'], " "))
+                }
+                InvalidSimpleStringPolicy::EncodeAsBulk => self.serialize_bytes(v.as_bytes()),
+            },
+            Some(prefix) => self.write_line(prefix, v),
+            None if self.config.prefer_simple_strings && !has_crlf(v) => self.write_line(b'+', v),
+            None => self.serialize_bytes(v.as_bytes()),
+        }
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if std::mem::take(&mut self.encoded_frame) {
+            self.start_value();
+            return self.write_all(v);
+        }
+        if v.len() > self.config.limits.max_bulk_len {
+            return Err(Error::BulkStringOverflow);
+        }
+        self.start_value();
         let prefix = format!("${}\r\n", self.buffer.format(v.len()));
-        self.writer.write_all(prefix.as_bytes())?;
-        self.writer.write_all(v)?;
-        self.writer.write_all(b"\r\n")?;
+        self.write_all(prefix.as_bytes())?;
+        self.write_all(v)?;
+        self.write_all(b"\r\n")?;
         Ok(())
     }
 
     fn serialize_none(self) -> Result<()> {
-        self.writer.write_all(b"$-1\r\n")?;
+        self.start_value();
+        self.write_all(self.config.null())?;
         Ok(())
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<()>
+    fn serialize_some<T>(self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -124,126 +811,303 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         self.serialize_none()
     }
 
-    fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
-        unimplemented!()
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        if name == NULL_BULK_TOKEN || name == NULL_ARRAY_TOKEN {
+            // RESP3 has a single kind of null.
+            let null: &[u8] = match self.config.version {
+                RespVersion::Resp2 if name == NULL_BULK_TOKEN => b"$-1\r\n",
+                RespVersion::Resp2 => b"*-1\r\n",
+                RespVersion::Resp3 => b"_\r\n",
+            };
+            self.start_value();
+            self.write_all(null)?;
+            Ok(())
+        } else if name == NULL_TOKEN {
+            self.start_value();
+            self.write_all(b"_\r\n")?;
+            Ok(())
+        } else {
+            self.serialize_unit()
+        }
     }
 
-    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<()> {
-        unimplemented!()
+    // Unit variants are written as their name, other variants as an array
+    // of their name followed by their content.
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<()> {
+        self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _: &'static str, _: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        unimplemented!()
+        match name {
+            SIMPLE_STRING_TOKEN => self.string_prefix = Some(b'+'),
+            ERROR_TOKEN => self.string_prefix = Some(b'-'),
+            UINTEGER_TOKEN => self.string_prefix = Some(b':'),
+            BIG_NUMBER_TOKEN => match self.config.version {
+                RespVersion::Resp2 => self.string_prefix = Some(b'$'),
+                RespVersion::Resp3 => self.string_prefix = Some(b'('),
+            },
+            REPLY_TOKEN => self.encoded_frame = true,
+            _ => {}
+        }
+        let result = value.serialize(&mut *self);
+        self.string_prefix = None;
+        self.encoded_frame = false;
+        result
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _: &'static str,
         _: u32,
-        _: &'static str,
-        _: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        unimplemented!()
+        self.open_array(Some(2), false)?;
+        self.serialize_str(variant)?;
+        value.serialize(&mut *self)?;
+        self.depth -= 1;
+        Ok(())
     }
 
+    // The header of a sequence is written with its first element, which
+    // doubles its length if a `Pair`.
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        match len {
-            Some(x) => self.writer.write_all((&format!("*{x}\r\n")).as_bytes())?,
-            None => self.writer.write_all((&format!("*-1\r\n")).as_bytes())?,
-        }
-        Ok(self)
+        self.open_array(len, true)
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
-        self.serialize_seq(Some(len))
+        self.open_array(Some(len), false)
     }
 
     fn serialize_tuple_struct(
         self,
-        _: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        self.serialize_seq(Some(len))
+        if name == PAIR_TOKEN {
+            return self.open_pair();
+        }
+        self.open_array(Some(len), false)
     }
 
     fn serialize_tuple_variant(
         self,
         _: &'static str,
         _: u32,
-        _: &'static str,
-        _: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        unimplemented!()
+        self.open_array(Some(len + 1), false)?;
+        self.serialize_str(variant)?;
+        Ok(self)
     }
 
-    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
-        unimplemented!()
+    // Maps and structs are written as RESP3 maps, or in RESP2 as flat arrays
+    // of alternating keys and values, the way Redis replies to HGETALL.
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        let Some(len) = len else {
+            return Err(Error::Unsupported { what: "map of unknown length" });
+        };
+        self.open_map(len)?;
+        if self.config.sort_map_keys {
+            self.sorted_maps.push(Vec::with_capacity(len));
+        }
+        Ok(self)
     }
 
-    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
-        unimplemented!()
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.open_map(len)
     }
 
     fn serialize_struct_variant(
         self,
         _: &'static str,
         _: u32,
-        _: &'static str,
-        _: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        unimplemented!()
+        self.open_array(Some(len * 2 + 1), false)?;
+        self.serialize_str(variant)?;
+        Ok(self)
     }
 }
 
-impl<'a, W: Write> ser::SerializeSeq for &'a mut Serializer<W> {
+impl<W: Write> ser::SerializeSeq for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
+    {
+        let depth = self.depth;
+        if self.unsized_seqs.last().is_none_or(|seq| seq.depth != depth) {
+            return value.serialize(&mut **self);
+        }
+        value.serialize(&mut **self)?;
+        let count = if self.pair_seqs.last() == Some(&depth) { 2 } else { 1 };
+        let limit = self.config.limits.max_array_len;
+        if let Some(seq) = self.unsized_seqs.last_mut() {
+            seq.len += count;
+            if seq.len > limit {
+                return Err(Error::ArrayTooLarge { declared: seq.len, limit });
+            }
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        let depth = self.depth;
+        if let Some(seq) = self.unsized_seqs.pop_if(|seq| seq.depth == depth) {
+            self.write_header(b'*', seq.len)?;
+            self.write_all(&seq.elements)?;
+        }
+        self.write_pending_header()?;
+        if self.pair_seqs.last() == Some(&self.depth) {
+            self.pair_seqs.pop();
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTuple for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
+        self.depth -= 1;
         Ok(())
     }
 }
+impl<W: Write> ser::SerializeTupleStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
 
-impl<'a, W: Write> ser::SerializeTuple for &'a mut Serializer<W> {
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    // A `Pair` is not an array of its own, it opened no level.
+    fn end(self) -> Result<()> {
+        if self.pair_seqs.last() != Some(&self.depth) {
+            self.depth -= 1;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
+        self.depth -= 1;
         Ok(())
     }
 }
-impl<'a, W: Write> ser::SerializeTupleStruct for &'a mut Serializer<W> {
+
+impl<W: Write> ser::SerializeMap for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.config.sort_map_keys {
+            return key.serialize(&mut **self);
+        }
+        let key = self.encode_entry(key)?;
+        if let Some(entries) = self.sorted_maps.last_mut() {
+            entries.push((key, Vec::new()));
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        if !self.config.sort_map_keys {
+            return value.serialize(&mut **self);
+        }
+        let value = self.encode_entry(value)?;
+        if let Some((_, slot)) = self.sorted_maps.last_mut().and_then(|entries| entries.last_mut()) {
+            *slot = value;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        if self.config.sort_map_keys {
+            let mut entries = self.sorted_maps.pop().unwrap_or_default();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            for (key, value) in entries {
+                self.write_all(&key)?;
+                self.write_all(&value)?;
+            }
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::Serializer::serialize_str(&mut **self, key)?;
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeStructVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::Serializer::serialize_str(&mut **self, key)?;
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
+        self.depth -= 1;
         Ok(())
     }
 }
@@ -257,9 +1121,12 @@ impl Serialize for RESPType {
         S: serde::Serializer,
     {
         match self {
-            RESPType::SimpleString(str) => ser.serialize_str(&("+".to_owned() + str)),
+            RESPType::SimpleString(str) => ser.serialize_newtype_struct(SIMPLE_STRING_TOKEN, str),
             RESPType::Integer(num) => ser.serialize_i64(*num),
-            RESPType::Error(err) => ser.serialize_str(&("-".to_owned() + err)),
+            RESPType::Double(v) => ser.serialize_f64(*v),
+            RESPType::Boolean(b) => ser.serialize_bool(*b),
+            RESPType::UInteger(num) => ser.serialize_newtype_struct(UINTEGER_TOKEN, &num.to_string()),
+            RESPType::Error(err) => ser.serialize_newtype_struct(ERROR_TOKEN, err),
             RESPType::BulkString(str) => ser.serialize_bytes(str),
             RESPType::Array(arr) => {
                 let mut ser = ser.serialize_seq(Some(arr.len()))?;
@@ -268,15 +1135,34 @@ impl Serialize for RESPType {
                 }
                 ser.end()
             },
-            RESPType::None => ser.serialize_none()
+            RESPType::None => ser.serialize_unit_struct(NULL_BULK_TOKEN),
+            RESPType::NullArray => ser.serialize_unit_struct(NULL_ARRAY_TOKEN),
+            RESPType::Null => ser.serialize_unit_struct(NULL_TOKEN),
+            RESPType::BigNumber(digits) => ser.serialize_newtype_struct(BIG_NUMBER_TOKEN, digits),
+            RESPType::Map(pairs) => {
+                let mut ser = ser.serialize_map(Some(pairs.len()))?;
+                for (key, value) in pairs {
+                    ser.serialize_entry(key, value)?;
+                }
+                ser.end()
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod ser_test {
-    use crate::ser::to_string;
-    use crate::RESPType;
+    use crate::error::ErrorKind;
+    use crate::ser::{to_string, to_vec, to_vec_with, to_writer, Serializer, SerializerBuilder};
+    use crate::ser::{to_writer_pipeline, to_writer_pipeline_with, transcode, write_null, InvalidSimpleStringPolicy, NullStyle};
+    use crate::ser::{encode_command_str, encode_command_str_with, write_bulk_from_reader, write_bulk_from_reader_with};
+    use crate::Pair;
+    use crate::{array, bulk, err, i64, simple};
+    use crate::RespVersion;
+    use crate::Error;
+    use std::io::{self, Write};
+    use serde::{Deserialize, Serialize};
+    use crate::{Limits, RESPType};
     use crate::Result;
 
     #[test]
@@ -337,4 +1223,697 @@ mod ser_test {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_null_array() -> Result<()> {
+        assert_eq!(to_string(&RESPType::NullArray)?, "*-1\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_crlf_in_simple_string() -> Result<()> {
+        for s in ["\r", "\n", "a\r\nb"] {
+            assert!(
+                to_string(&RESPType::SimpleString(s.to_owned()))
+                    .is_err_and(|err| err.kind() == ErrorKind::InvalidSimpleString)
+            );
+            assert!(
+                to_string(&RESPType::Error(s.to_owned()))
+                    .is_err_and(|err| err.kind() == ErrorKind::InvalidSimpleString)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_bulk_string() -> Result<()> {
+        let resp_bstr = RESPType::BulkString(vec![0xff, 0x00, 0xfe]);
+        assert_eq!(to_vec(&resp_bstr)?, b"$3\r\n\xff\x00\xfe\r\n");
+        assert_eq!(crate::from_slice::<RESPType>(&to_vec(&resp_bstr)?)?, resp_bstr);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_bulk_string_overflow() -> Result<()> {
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf).with_max_bulk_len(4);
+        RESPType::BulkString(b"four".to_vec()).serialize(&mut ser)?;
+        assert!(
+            RESPType::BulkString(b"fives".to_vec())
+                .serialize(&mut ser)
+                .is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow)
+        );
+        assert!("fives".serialize(&mut ser).is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow));
+        assert_eq!(buf, b"$4\r\nfour\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_limits() -> Result<()> {
+        let limits = Limits { max_bulk_len: 3, max_array_len: 2, ..Limits::default() };
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf).with_limits(limits);
+        assert!(
+            RESPType::BulkString(b"four".to_vec())
+                .serialize(&mut ser)
+                .is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow)
+        );
+        assert!(
+            vec![1, 2, 3]
+                .serialize(&mut ser)
+                .is_err_and(|err| err.kind() == ErrorKind::ArrayTooLarge)
+        );
+        // nothing is written for rejected values
+        assert!(buf.is_empty());
+
+        // the same limits reject the same frames when deserializing
+        let mut de = crate::de::Deserializer::from_str("$4\r\nfour\r\n").with_limits(limits);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| err.kind() == ErrorKind::BulkStringOverflow)
+        );
+        let mut de = crate::de::Deserializer::from_str("*3\r\n:1\r\n:2\r\n:3\r\n").with_limits(limits);
+        assert!(
+            RESPType::deserialize(&mut de)
+                .is_err_and(|err| err.kind() == ErrorKind::ArrayTooLarge)
+        );
+        Ok(())
+    }
+
+    // Accepts at most `chunk` bytes per call, fails once `capacity` bytes are in.
+    struct FlakyWriter {
+        buf: Vec<u8>,
+        chunk: usize,
+        capacity: usize,
+        interrupted: bool,
+    }
+
+    impl Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            // interrupt every other call
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(self.chunk).min(self.capacity - self.buf.len());
+            if n == 0 {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.buf.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_partial_write() -> Result<()> {
+        let value = RESPType::Array(vec![RESPType::ok(), RESPType::BulkString(b"hello".to_vec())]);
+        let frame = to_vec(&value)?;
+        let mut writer = FlakyWriter { buf: Vec::new(), chunk: 3, capacity: usize::MAX, interrupted: false };
+        to_writer(&value, &mut writer)?;
+        assert_eq!(writer.buf, frame);
+
+        let mut writer = FlakyWriter { buf: Vec::new(), chunk: 3, capacity: 10, interrupted: false };
+        let err = to_writer(&value, &mut writer).unwrap_err();
+        assert!(
+            matches!(&err, Error::IoError { err, written: 10 } if err.kind() == io::ErrorKind::WouldBlock)
+        );
+        assert_eq!(writer.buf, frame[..10]);
+        Ok(())
+    }
+
+    // Records whether it was flushed.
+    #[derive(Default)]
+    struct FlushRecorder {
+        buf: Vec<u8>,
+        flushed: bool,
+    }
+
+    impl Write for FlushRecorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.flushed = false;
+            self.buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush() -> Result<()> {
+        let mut writer = FlushRecorder::default();
+        to_writer(&RESPType::ok(), &mut writer)?;
+        assert!(!writer.flushed);
+        let mut ser = Serializer::new(&mut writer);
+        ser.flush()?;
+        assert!(writer.flushed);
+
+        let mut writer = FlushRecorder::default();
+        let commands = [array!(bulk!("GET"), bulk!("foo")), array!(bulk!("GET"), bulk!("bar"))];
+        to_writer_pipeline(&commands, &mut writer)?;
+        assert!(writer.flushed);
+        assert_eq!(writer.buf, b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n*2\r\n$3\r\nGET\r\n$3\r\nbar\r\n");
+
+        let mut writer = FlushRecorder::default();
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        to_writer_pipeline_with(&[RESPType::None, RESPType::Boolean(true)], &mut writer, &resp3)?;
+        assert!(writer.flushed);
+        assert_eq!(writer.buf, b"_\r\n#t\r\n");
+        let limited = SerializerBuilder::new().max_output_bytes(30);
+        assert!(
+            to_writer_pipeline_with(&commands, &mut Vec::new(), &limited)
+                .is_err_and(|err| err.kind() == ErrorKind::OutputTooLarge)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_array() -> Result<()> {
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf);
+        let mut array = ser.begin_streamed_array()?;
+        array.push(&1)?;
+        array.push(&RESPType::ok())?;
+        array.push("foo")?;
+        array.finish()?;
+        assert_eq!(buf, b"*?\r\n:1\r\n+OK\r\n$3\r\nfoo\r\n.\r\n");
+        Ok(())
+    }
+
+    #[derive(Serialize)]
+    struct Probe {
+        flag: bool,
+        unit: (),
+        kinds: Vec<Kind>,
+    }
+
+    #[derive(Serialize)]
+    enum Kind {
+        Empty,
+        Wrap(i64),
+        Pair(i64, i64),
+        Named { x: i64 },
+    }
+
+    #[test]
+    fn test_struct() -> Result<()> {
+        let probe = Probe {
+            flag: false,
+            unit: (),
+            kinds: vec![Kind::Empty, Kind::Wrap(3), Kind::Pair(1, 2), Kind::Named { x: 1 }],
+        };
+        assert_eq!(
+            to_string(&probe)?,
+            concat!(
+                "*6\r\n$4\r\nflag\r\n:0\r\n$4\r\nunit\r\n$-1\r\n$5\r\nkinds\r\n*4\r\n",
+                "$5\r\nEmpty\r\n",
+                "*2\r\n$4\r\nWrap\r\n:3\r\n",
+                "*3\r\n$4\r\nPair\r\n:1\r\n:2\r\n",
+                "*3\r\n$5\r\nNamed\r\n$1\r\nx\r\n:1\r\n",
+            )
+        );
+        Ok(())
+    }
+
+    // Serializes a map without telling its length.
+    struct UnsizedMap;
+
+    impl Serialize for UnsizedMap {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+            ser.serialize_map(None)?.end()
+        }
+    }
+
+    // Serializes the even numbers of a list, whose count is unknown until
+    // they have all been filtered.
+    struct Evens(Vec<i64>);
+
+    impl Serialize for Evens {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+            ser.collect_seq(self.0.iter().filter(|&n| n % 2 == 0))
+        }
+    }
+
+    // Pairs of the even numbers of a list and their half.
+    struct EvenHalves(Vec<i64>);
+
+    impl Serialize for EvenHalves {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+            ser.collect_seq(self.0.iter().filter(|&n| n % 2 == 0).map(|n| Pair(n, n / 2)))
+        }
+    }
+
+    // Every natural number, a sequence without end.
+    struct Naturals;
+
+    impl Serialize for Naturals {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+            ser.collect_seq(0_u64..)
+        }
+    }
+
+    #[test]
+    fn test_unsized_seq() -> Result<()> {
+        assert_eq!(to_string(&Evens(vec![1, 2, 3, 4]))?, "*2\r\n:2\r\n:4\r\n");
+        assert_eq!(to_string(&Evens(vec![1, 3]))?, "*0\r\n");
+        let nested = vec![Evens(vec![2, 3]), Evens(vec![]), Evens(vec![4, 6])];
+        let frame = to_vec(&nested)?;
+        assert_eq!(frame, b"*3\r\n*1\r\n:2\r\n*0\r\n*2\r\n:4\r\n:6\r\n");
+        assert_eq!(crate::from_slice::<Vec<Vec<i64>>>(&frame)?, [vec![2], vec![], vec![4, 6]]);
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf);
+        (Evens(vec![2]), Evens(vec![4])).serialize(&mut ser)?;
+        assert_eq!(ser.stats().frames, 1);
+        let mut limits = Limits::default();
+        limits.set_max_array_len(2);
+        let config = SerializerBuilder::new().with_limits(limits);
+        assert!(
+            to_vec_with(&Evens(vec![2, 4, 6]), &config)
+                .is_err_and(|err| matches!(err, Error::ArrayTooLarge { declared: 3, limit: 2 }))
+        );
+
+        // pairs count twice
+        let frame = to_vec(&EvenHalves(vec![1, 2, 3, 4]))?;
+        assert_eq!(frame, b"*4\r\n:2\r\n:1\r\n:4\r\n:2\r\n");
+        assert_eq!(crate::from_slice::<Vec<Pair<i64, i64>>>(&frame)?, [Pair(2, 1), Pair(4, 2)]);
+        assert_eq!(to_vec(&vec![EvenHalves(vec![2]), EvenHalves(vec![])])?, b"*2\r\n*2\r\n:2\r\n:1\r\n*0\r\n");
+        assert!(
+            to_vec_with(&EvenHalves(vec![2, 4]), &SerializerBuilder::new().with_max_array_len(3))
+                .is_err_and(|err| matches!(err, Error::ArrayTooLarge { declared: 4, limit: 3 }))
+        );
+
+        // the output limit applies while the elements are buffered
+        assert!(
+            to_vec_with(&Naturals, &SerializerBuilder::new().max_output_bytes(1024))
+                .is_err_and(|err| matches!(err, Error::OutputTooLarge { limit: 1024 }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_map_keys() -> Result<()> {
+        use std::collections::HashMap;
+
+        let sorted = SerializerBuilder::new().sort_map_keys(true);
+        let build = || (0..50).map(|i| (format!("key:{i}"), i)).collect::<HashMap<_, _>>();
+        let frame = to_vec_with(&build(), &sorted)?;
+        assert_eq!(to_vec_with(&build(), &sorted)?, frame);
+        assert_eq!(crate::from_slice::<HashMap<String, i64>>(&frame)?, build());
+        let map = HashMap::from([("b", vec![3]), ("a", vec![1, 2]), ("c", vec![])]);
+        let frame = b"*6\r\n$1\r\na\r\n*2\r\n:1\r\n:2\r\n$1\r\nb\r\n*1\r\n:3\r\n$1\r\nc\r\n*0\r\n";
+        assert_eq!(to_vec_with(&map, &sorted)?, frame);
+        let nested = HashMap::from([(2, HashMap::from([("y", 1), ("x", 2)])), (1, HashMap::new())]);
+        let resp3 = sorted.clone().version(RespVersion::Resp3);
+        let frame = b"%2\r\n:1\r\n%0\r\n:2\r\n%2\r\n$1\r\nx\r\n:2\r\n$1\r\ny\r\n:1\r\n";
+        assert_eq!(to_vec_with(&nested, &resp3)?, frame);
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf).sort_map_keys(true);
+        map.serialize(&mut ser)?;
+        assert_eq!(ser.stats().frames, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resp3_map() -> Result<()> {
+        use std::collections::BTreeMap;
+
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        let config = BTreeMap::from([("maxmemory", vec!["0"]), ("save", vec!["3600", "1"])]);
+        let frame = b"%2\r\n$9\r\nmaxmemory\r\n*1\r\n$1\r\n0\r\n$4\r\nsave\r\n*2\r\n$4\r\n3600\r\n$1\r\n1\r\n";
+        assert_eq!(to_vec_with(&config, &resp3)?, frame);
+        assert_eq!(crate::from_slice::<BTreeMap<String, Vec<String>>>(frame)?.len(), 2);
+        assert_eq!(to_vec_with(&BTreeMap::<String, i64>::new(), &resp3)?, b"%0\r\n");
+        assert_eq!(to_vec_with(&RESPType::Map(vec![]), &resp3)?, b"%0\r\n");
+        assert_eq!(crate::from_slice::<RESPType>(b"%0\r\n")?, RESPType::Map(vec![]));
+
+        let map = RESPType::Map(vec![(simple!("a".to_owned()), array![i64!(1), bulk!("x")])]);
+        let frame = b"%1\r\n+a\r\n*2\r\n:1\r\n$1\r\nx\r\n";
+        assert_eq!(to_vec_with(&map, &resp3)?, frame);
+        assert_eq!(crate::from_slice::<RESPType>(frame)?, map);
+        Ok(())
+    }
+
+    #[test]
+    fn test_double() -> Result<()> {
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        let cases = [(1.5, ",1.5\r\n"), (-0.25, ",-0.25\r\n"), (10.0, ",10\r\n"), (f64::INFINITY, ",inf\r\n"), (f64::NEG_INFINITY, ",-inf\r\n")];
+        for (v, frame) in cases {
+            assert_eq!(to_vec_with(&v, &resp3)?, frame.as_bytes());
+            assert_eq!(to_vec_with(&RESPType::Double(v), &resp3)?, frame.as_bytes());
+            assert_eq!(crate::from_str::<RESPType>(frame)?, RESPType::Double(v));
+            assert_eq!(crate::from_slice::<f64>(&to_vec(&v)?)?, v);
+        }
+        assert_eq!(to_vec_with(&f64::NAN, &resp3)?, b",nan\r\n");
+        assert_eq!(crate::from_str::<RESPType>(",nan\r\n")?, RESPType::Double(f64::NAN));
+        assert_eq!(to_vec_with(&0.1f32, &resp3)?, b",0.1\r\n");
+        assert_eq!(to_string(&1.5f64)?, "$3\r\n1.5\r\n");
+        assert_eq!(to_string(&RESPType::Double(f64::NEG_INFINITY))?, "$4\r\n-inf\r\n");
+        assert_eq!(crate::to_value(&2.5f64)?, RESPType::Double(2.5));
+        assert_eq!(crate::from_value::<f64>(RESPType::Double(2.5))?, 2.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_unsupported() -> Result<()> {
+        assert_eq!(to_string(&(i64::MAX as u64))?, ":9223372036854775807\r\n");
+        assert!(to_string(&UnsizedMap).is_err_and(|err| err.kind() == ErrorKind::Unsupported));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let mut buf = Vec::new();
+        let mut ser = Serializer::new(&mut buf);
+        RESPType::ok().serialize(&mut ser)?;
+        vec![Some(1), None].serialize(&mut ser)?;
+        RESPType::Array(vec![RESPType::Array(vec![RESPType::NullArray])]).serialize(&mut ser)?;
+        let stats = ser.stats();
+        assert_eq!(stats.frames, 3);
+        assert_eq!(stats.bytes, buf.len());
+        assert_eq!(stats.bytes, 5 + 13 + 13);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder() -> Result<()> {
+        let value = (Some("ok"), None::<i64>, RESPType::Error("ERR a\r\nb".to_owned()), RESPType::NullArray);
+        let resp2 = SerializerBuilder::new().on_invalid_simple_string(InvalidSimpleStringPolicy::ReplaceWithSpace);
+        assert_eq!(to_vec_with(&value, &resp2)?, b"*4\r\n$2\r\nok\r\n$-1\r\n-ERR a  b\r\n*-1\r\n");
+        let resp3 = resp2.clone().version(RespVersion::Resp3).prefer_simple_strings(true);
+        assert_eq!(to_vec_with(&value, &resp3)?, b"*4\r\n+ok\r\n_\r\n-ERR a  b\r\n_\r\n");
+        let null_array = resp2.null_style(NullStyle::NullArray);
+        assert_eq!(to_vec_with(&value, &null_array)?, b"*4\r\n$2\r\nok\r\n*-1\r\n-ERR a  b\r\n*-1\r\n");
+        // the default builder fails on the error reply, like `to_vec`
+        assert!(
+            to_vec_with(&value, &SerializerBuilder::default())
+                .is_err_and(|err| err.kind() == ErrorKind::InvalidSimpleString)
+        );
+
+        // a simple string is only preferred when it is valid
+        let mut buf = Vec::new();
+        let mut ser = Serializer::with_options(&mut buf, SerializerBuilder::new().prefer_simple_strings(true));
+        "a\nb".serialize(&mut ser)?;
+        assert_eq!(buf, b"$3\r\na\nb\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_style() -> Result<()> {
+        let value = (None::<i64>, ());
+        for (style, null) in [
+            (NullStyle::NullBulk, "$-1\r\n"),
+            (NullStyle::NullArray, "*-1\r\n"),
+            (NullStyle::Resp3Null, "_\r\n"),
+        ] {
+            let config = SerializerBuilder::new().null_style(style);
+            assert_eq!(to_vec_with(&value, &config)?, format!("*2\r\n{null}{null}").into_bytes());
+        }
+        assert_eq!(to_string(&None::<i64>)?, "$-1\r\n");
+
+        // nulls read into RESPType are written back as they were received
+        let config = SerializerBuilder::new().null_style(NullStyle::Resp3Null);
+        for frame in ["$-1\r\n", "*-1\r\n", "_\r\n"] {
+            let value: RESPType = crate::from_str(frame)?;
+            assert_eq!(to_vec_with(&value, &config)?, frame.as_bytes());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefer_simple_strings() -> Result<()> {
+        let config = SerializerBuilder::new().prefer_simple_strings(true);
+        assert_eq!(to_vec_with(&"OK", &config)?, b"+OK\r\n");
+        assert_eq!(to_vec_with(&"two\nlines", &config)?, b"$9\r\ntwo\nlines\r\n");
+        assert_eq!(to_vec_with(&"carriage\r", &config)?, b"$9\r\ncarriage\r\r\n");
+        assert_eq!(to_vec_with(&bulk!("OK"), &config)?, b"$2\r\nOK\r\n");
+        // off by default
+        assert_eq!(to_string(&"OK")?, "$2\r\nOK\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_depth() -> Result<()> {
+        let config = SerializerBuilder::new().max_depth(2);
+        let nested = array!(array!(i64!(1)), i64!(2));
+        assert_eq!(to_vec_with(&nested, &config)?, to_vec(&nested)?);
+        assert!(
+            to_vec_with(&array!(array!(array!())), &config)
+                .is_err_and(|err| matches!(err, Error::DepthLimitExceeded { limit: 2 }))
+        );
+        // any sequence opens an array
+        assert!(
+            to_vec_with(&vec![Some(vec![1])], &SerializerBuilder::new().max_depth(1))
+                .is_err_and(|err| err.kind() == ErrorKind::DepthLimitExceeded)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_output_bytes() -> Result<()> {
+        let value = array!(bulk!("hello"), bulk!("world"));
+        let frame = to_vec(&value)?;
+        assert_eq!(to_vec_with(&value, &SerializerBuilder::new().max_output_bytes(frame.len()))?, frame);
+
+        let mut buf = Vec::new();
+        let mut ser = SerializerBuilder::new().max_output_bytes(frame.len() - 1).build(&mut buf);
+        assert!(
+            value.serialize(&mut ser)
+                .is_err_and(|err| matches!(err, Error::OutputTooLarge { limit } if limit == frame.len() - 1))
+        );
+        assert!(buf.len() < frame.len());
+        assert_eq!(buf, frame[..buf.len()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool() -> Result<()> {
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        assert_eq!(to_string(&true)?, ":1\r\n");
+        assert_eq!(to_vec_with(&true, &resp3)?, b"#t\r\n");
+        assert_eq!(to_vec_with(&(false, 0), &resp3)?, b"*2\r\n#f\r\n:0\r\n");
+        assert_eq!(to_vec_with(&vec![true, false], &resp3)?, b"*2\r\n#t\r\n#f\r\n");
+        assert_eq!(to_vec_with(&vec![vec![true]], &resp3)?, b"*1\r\n*1\r\n#t\r\n");
+        assert_eq!(to_vec_with(&RESPType::Boolean(true), &resp3)?, b"#t\r\n");
+        assert_eq!(to_vec_with(&RESPType::Boolean(false), &resp3)?, b"#f\r\n");
+        assert_eq!(to_string(&RESPType::Boolean(true))?, ":1\r\n");
+        assert_eq!(to_string(&vec![true, false])?, "*2\r\n:1\r\n:0\r\n");
+        for config in [SerializerBuilder::new(), resp3] {
+            for v in [true, false] {
+                assert_eq!(crate::from_slice::<bool>(&to_vec_with(&v, &config)?)?, v);
+            }
+        }
+        assert!(
+            crate::from_str::<bool>("#x\r\n")
+                .is_err_and(|err| err.kind() == ErrorKind::UnexpectedSign)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_number() -> Result<()> {
+        let big = RESPType::BigNumber("-3492890328409238509324850943850943825024385".to_owned());
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        assert_eq!(to_vec_with(&big, &resp3)?, b"(-3492890328409238509324850943850943825024385\r\n");
+        assert_eq!(crate::from_slice::<RESPType>(&to_vec_with(&big, &resp3)?)?, big);
+        assert_eq!(to_string(&big)?, "$44\r\n-3492890328409238509324850943850943825024385\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_integers() -> Result<()> {
+        let resp3 = SerializerBuilder::new().version(RespVersion::Resp3);
+        assert_eq!(to_string(&7u128)?, ":7\r\n");
+        assert_eq!(to_string(&u128::MAX)?, "$39\r\n340282366920938463463374607431768211455\r\n");
+        assert_eq!(to_vec_with(&i128::MIN, &resp3)?, b"(-170141183460469231731687303715884105728\r\n");
+        for config in [SerializerBuilder::new(), resp3] {
+            let frame = to_vec_with(&u128::MAX, &config)?;
+            assert_eq!(crate::from_slice::<u128>(&frame)?, u128::MAX);
+            assert_eq!(crate::from_value::<u128>(crate::from_slice(&frame)?)?, u128::MAX);
+            assert!(crate::from_slice::<i128>(&frame).is_err_and(|err| err.kind() == ErrorKind::ParseIntError));
+            let frame = to_vec_with(&(i128::MIN, -1i128), &config)?;
+            assert_eq!(crate::from_slice::<(i128, i128)>(&frame)?, (i128::MIN, -1));
+            assert!(crate::from_slice::<(u128, u128)>(&frame).is_err_and(|err| err.kind() == ErrorKind::ParseIntError));
+        }
+        assert_eq!(crate::to_value(&u128::MAX)?, bulk!("340282366920938463463374607431768211455"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pair() -> Result<()> {
+        let fields = vec![Pair("a".to_owned(), 1), Pair("b".to_owned(), 2)];
+        assert_eq!(to_string(&fields)?, "*4\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n");
+        assert_eq!(to_string(&Vec::<Pair<String, i64>>::new())?, "*0\r\n");
+        let nested = vec![Pair("h", vec![Pair("f", vec![1])]), Pair("e", vec![])];
+        assert_eq!(
+            to_string(&nested)?,
+            "*4\r\n$1\r\nh\r\n*2\r\n$1\r\nf\r\n*1\r\n:1\r\n$1\r\ne\r\n*0\r\n"
+        );
+        // pairs are no level of their own
+        let config = SerializerBuilder::new().max_depth(3);
+        assert_eq!(to_vec_with(&nested, &config)?, to_vec(&nested)?);
+        assert!(
+            to_vec_with(&fields, &SerializerBuilder::new().with_max_array_len(3))
+                .is_err_and(|err| matches!(err, Error::ArrayTooLarge { declared: 4, limit: 3 }))
+        );
+        assert!(to_vec(&Pair(1, 2)).is_err_and(|err| err.kind() == ErrorKind::Unsupported));
+        assert!(to_vec(&(1, Pair(1, 2))).is_err_and(|err| err.kind() == ErrorKind::Unsupported));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_command_str() -> Result<()> {
+        let args = ["HSET", "h", "f", "v"];
+        let mut buf = Vec::new();
+        encode_command_str(&args, &mut buf)?;
+        assert_eq!(buf, b"*4\r\n$4\r\nHSET\r\n$1\r\nh\r\n$1\r\nf\r\n$1\r\nv\r\n");
+        let resp = RESPType::Array(args.iter().map(|arg| bulk!(*arg)).collect());
+        assert_eq!(buf, to_vec(&resp)?);
+        let mut buf = Vec::new();
+        encode_command_str(&[], &mut buf)?;
+        assert_eq!(buf, b"*0\r\n");
+
+        // limits are checked before anything is written
+        let frame = to_vec(&resp)?;
+        let mut buf = Vec::new();
+        encode_command_str_with(&args, &mut buf, &SerializerBuilder::new().max_output_bytes(frame.len()))?;
+        assert_eq!(buf, frame);
+        let cases = [
+            (SerializerBuilder::new().with_max_bulk_len(3), ErrorKind::BulkStringOverflow),
+            (SerializerBuilder::new().with_max_array_len(3), ErrorKind::ArrayTooLarge),
+            (SerializerBuilder::new().max_output_bytes(frame.len() - 1), ErrorKind::OutputTooLarge),
+        ];
+        for (config, kind) in cases {
+            let mut buf = Vec::new();
+            assert!(encode_command_str_with(&args, &mut buf, &config).is_err_and(|err| err.kind() == kind));
+            assert!(buf.is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_bulk_from_reader() -> Result<()> {
+        let payload: Vec<u8> = (0..1024).map(|i| (i % 251) as u8).collect();
+        let mut src = io::Cursor::new(payload.clone());
+        let mut buf = Vec::new();
+        write_bulk_from_reader(&mut buf, 1024, &mut src)?;
+        assert_eq!(buf, to_vec(&RESPType::BulkString(payload.clone()))?);
+        let mut src = io::Cursor::new(payload);
+        let mut buf = Vec::new();
+        write_bulk_from_reader(&mut buf, 1000, &mut src)?;
+        assert_eq!(&buf[..7], b"$1000\r\n");
+        assert_eq!(src.position(), 1000);
+        let mut short = &b"abc"[..];
+        assert!(write_bulk_from_reader(&mut Vec::new(), 4, &mut short).is_err_and(|err| err.kind() == ErrorKind::Eof));
+
+        let cases = [
+            (SerializerBuilder::new().with_max_bulk_len(999), ErrorKind::BulkStringOverflow),
+            (SerializerBuilder::new().max_output_bytes(1008), ErrorKind::OutputTooLarge),
+        ];
+        for (config, kind) in cases {
+            let mut src = &b"abc"[..];
+            let mut buf = Vec::new();
+            assert!(
+                write_bulk_from_reader_with(&mut buf, 1000, &mut src, &config).is_err_and(|err| err.kind() == kind)
+            );
+            assert!(buf.is_empty());
+            assert_eq!(src, b"abc");
+        }
+        let mut buf = Vec::new();
+        write_bulk_from_reader_with(&mut buf, 3, &mut &b"abc"[..], &SerializerBuilder::new().max_output_bytes(9))?;
+        assert_eq!(buf, b"$3\r\nabc\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode() -> Result<()> {
+        let resp3 = b"%2\r\n+a\r\n:1\r\n+b\r\n_\r\n";
+        let map = crate::from_slice::<RESPType>(resp3)?;
+        assert_eq!(map, RESPType::Map(vec![(simple!("a".to_owned()), i64!(1)), (simple!("b".to_owned()), RESPType::Null)]));
+        let resp2 = transcode(&map, RespVersion::Resp2)?;
+        assert_eq!(resp2, b"*4\r\n+a\r\n:1\r\n+b\r\n$-1\r\n");
+        assert_eq!(transcode(&map, RespVersion::Resp3)?, resp3);
+        // the map is a plain array once read from RESP2
+        let arr = crate::from_slice::<RESPType>(&resp2)?;
+        assert_eq!(transcode(&arr, RespVersion::Resp3)?, b"*4\r\n+a\r\n:1\r\n+b\r\n_\r\n");
+
+        assert_eq!(transcode(&RESPType::None, RespVersion::Resp3)?, b"_\r\n");
+        assert_eq!(transcode(&RESPType::NullArray, RespVersion::Resp3)?, b"_\r\n");
+        assert_eq!(transcode(&RESPType::Null, RespVersion::Resp2)?, b"$-1\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_simple_string_policy() -> Result<()> {
+        let value = array!(simple!("a\r\nb".to_owned()), err!("ERR a\r\nb".to_owned()));
+        let config = |policy| SerializerBuilder::new().on_invalid_simple_string(policy);
+        assert!(
+            to_vec_with(&value, &config(InvalidSimpleStringPolicy::Error))
+                .is_err_and(|err| err.kind() == ErrorKind::InvalidSimpleString)
+        );
+        assert_eq!(
+            to_vec_with(&value, &config(InvalidSimpleStringPolicy::ReplaceWithSpace))?,
+            b"*2\r\n+a  b\r\n-ERR a  b\r\n"
+        );
+        assert_eq!(
+            to_vec_with(&value, &config(InvalidSimpleStringPolicy::EncodeAsBulk))?,
+            b"*2\r\n$4\r\na\r\nb\r\n$8\r\nERR a\r\nb\r\n"
+        );
+        // valid strings are left alone
+        let valid = array!(simple!("ab".to_owned()), err!("ERR".to_owned()));
+        assert_eq!(to_vec_with(&valid, &config(InvalidSimpleStringPolicy::EncodeAsBulk))?, b"*2\r\n+ab\r\n-ERR\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_null() -> Result<()> {
+        let mut buf = Vec::new();
+        write_null(&mut buf, RespVersion::Resp2)?;
+        write_null(&mut buf, RespVersion::Resp3)?;
+        assert_eq!(buf, b"$-1\r\n_\r\n");
+        assert_eq!(to_string(&RESPType::null(RespVersion::Resp2))?, "$-1\r\n");
+        assert_eq!(to_string(&RESPType::null(RespVersion::Resp3))?, "_\r\n");
+        assert_eq!(serde_json::to_string(&RESPType::Null).unwrap(), "null");
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_string() -> Result<()> {
+        assert_eq!(to_string(&"hello")?, "$5\r\nhello\r\n");
+        assert_eq!(to_string(&'c')?, "$1\r\nc\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_other_serializer() -> Result<()> {
+        let values = [
+            (RESPType::SimpleString("hi".to_owned()), r#""hi""#),
+            (RESPType::Error("ERR oops".to_owned()), r#""ERR oops""#),
+            (RESPType::Integer(-3), "-3"),
+            (RESPType::None, "null"),
+            (
+                RESPType::Array(vec![RESPType::SimpleString("a".to_owned()), RESPType::Integer(1)]),
+                r#"["a",1]"#,
+            ),
+        ];
+        for (value, json) in values {
+            assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        }
+        // values json can represent round-trip through it, and through RESP
+        let value = RESPType::Array(vec![
+            RESPType::SimpleString("hi".to_owned()),
+            RESPType::Integer(42),
+            RESPType::None,
+        ]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<RESPType>(&json).unwrap(), value);
+        assert_eq!(crate::from_str::<RESPType>(&to_string(&value)?)?, value);
+        Ok(())
+    }
 }
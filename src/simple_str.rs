@@ -0,0 +1,65 @@
+use crate::ser::SIMPLE_STRING_TOKEN;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A string written as a simple string, `+...\r\n`, where a `String` would
+/// be written as a bulk string.
+///
+/// The text must not contain `\r` or `\n`, what happens otherwise depends on
+/// the serializer's `on_invalid_simple_string` policy. It is read from a
+/// simple or a bulk string, like a `String`.
+///
+/// ```
+/// use serde_resp::{to_vec, SimpleStr};
+///
+/// assert_eq!(to_vec(&SimpleStr::from("OK"))?, b"+OK\r\n");
+/// assert_eq!(to_vec(&"OK")?, b"$2\r\nOK\r\n");
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct SimpleStr(pub String);
+
+impl From<String> for SimpleStr {
+    fn from(s: String) -> Self {
+        SimpleStr(s)
+    }
+}
+
+impl From<&str> for SimpleStr {
+    fn from(s: &str) -> Self {
+        SimpleStr(s.to_owned())
+    }
+}
+
+impl Serialize for SimpleStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(SIMPLE_STRING_TOKEN, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SimpleStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SimpleStr)
+    }
+}
+
+#[cfg(test)]
+mod simple_str_test {
+    use crate::{from_slice, to_vec, Result, SimpleStr};
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn test_simple_str_field() -> Result<()> {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Status {
+            state: SimpleStr,
+            detail: String,
+        }
+
+        let status = Status { state: "OK".into(), detail: "ready".to_owned() };
+        let frame = to_vec(&status)?;
+        assert_eq!(frame, b"*4\r\n$5\r\nstate\r\n+OK\r\n$6\r\ndetail\r\n$5\r\nready\r\n");
+        assert_eq!(from_slice::<Status>(&frame)?, status);
+        assert!(to_vec(&SimpleStr::from("a\r\nb")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use smallvec::{Array, SmallVec};
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+/// Read and write a `SmallVec` of bytes as a bulk string, for use with
+/// `#[serde(with = "serde_resp::small_bytes")]`, as `serde_bytes` does for `Vec<u8>`.
+///
+/// Without it a `SmallVec<[u8; N]>` is an array of integers. Payloads of at
+/// most `N` bytes are kept inline, without any heap allocation.
+///
+/// ```
+/// use serde::Deserialize;
+/// use smallvec::SmallVec;
+///
+/// #[derive(Deserialize)]
+/// struct Entry {
+///     #[serde(with = "serde_resp::small_bytes")]
+///     value: SmallVec<[u8; 32]>,
+/// }
+///
+/// let entry: Entry = serde_resp::from_slice(b"*2\r\n$5\r\nvalue\r\n$2\r\nhi\r\n")?;
+/// assert_eq!(&entry.value[..], b"hi");
+/// assert!(!entry.value.spilled());
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+pub fn deserialize<'de, D, A>(deserializer: D) -> Result<SmallVec<A>, D::Error>
+where
+    D: Deserializer<'de>,
+    A: Array<Item = u8>,
+{
+    deserializer.deserialize_byte_buf(SmallBytesVisitor(PhantomData))
+}
+
+pub fn serialize<S, A>(bytes: &SmallVec<A>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    A: Array<Item = u8>,
+{
+    serializer.serialize_bytes(bytes)
+}
+
+struct SmallBytesVisitor<A>(PhantomData<A>);
+
+impl<A: Array<Item = u8>> Visitor<'_> for SmallBytesVisitor<A> {
+    type Value = SmallVec<A>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(SmallVec::from_slice(v))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        // a payload that fits is moved inline rather than keeping the allocation
+        Ok(SmallVec::from_slice(&v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(SmallVec::from_slice(v.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod small_bytes_test {
+    use crate::{from_slice, to_vec, Result};
+    use serde::{Deserialize, Serialize};
+    use smallvec::SmallVec;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Small(#[serde(with = "crate::small_bytes")] SmallVec<[u8; 32]>);
+
+    #[test]
+    fn test_small_bytes() -> Result<()> {
+        let frame = b"$16\r\n0123456789\xff\x00abcd\r\n";
+        let Small(bytes) = from_slice(frame)?;
+        assert_eq!(&bytes[..], b"0123456789\xff\x00abcd");
+        assert!(!bytes.spilled());
+        assert_eq!(to_vec(&Small(bytes))?, frame);
+        let Small(long) = from_slice(format!("${}\r\n{}\r\n", 40, "x".repeat(40)).as_bytes())?;
+        assert_eq!(long.len(), 40);
+        assert!(long.spilled());
+        Ok(())
+    }
+}
@@ -0,0 +1,1244 @@
+use crate::de::{parse_f64, RawFrame, RESP_TYPE_TOKEN};
+use crate::pair::PAIR_TOKEN;
+use crate::reply::{Reply, REPLY_TOKEN};
+use crate::ser::{BIG_NUMBER_TOKEN, ERROR_TOKEN, NULL_ARRAY_TOKEN, NULL_BULK_TOKEN, NULL_TOKEN, SIMPLE_STRING_TOKEN};
+use crate::ser::UINTEGER_TOKEN;
+use crate::{Error, RESPType, RespError, Result};
+use serde::de::value::{SeqDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::ser::{self, Serialize};
+use std::borrow::Cow;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::vec;
+
+/// Deserialize a `T` from an already parsed value, without encoding it again.
+///
+/// The value is read the way the default deserializer reads its encoding:
+/// maps and structs come from flat arrays of keys and values, and an error
+/// reply is returned as `Error::ErrorReply` unless `T` is `RESPType`.
+///
+/// ```
+/// use serde_resp::{from_value, RESPType};
+///
+/// let reply = RESPType::Array(vec![RESPType::Integer(1), RESPType::None]);
+/// assert_eq!(from_value::<Vec<Option<i64>>>(reply)?, [Some(1), None]);
+/// # Ok::<(), serde_resp::Error>(())
+/// ```
+pub fn from_value<T>(value: RESPType) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(ValueDeserializer::new(value))
+}
+
+/// Serialize `value` into a `RESPType`, as the default serializer would encode it.
+pub fn to_value<T>(value: &T) -> Result<RESPType>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)
+}
+
+/// Deserializer reading from a [`RESPType`] tree, see [`from_value`].
+pub struct ValueDeserializer {
+    value: RESPType,
+}
+
+impl ValueDeserializer {
+    pub fn new(value: RESPType) -> Self {
+        ValueDeserializer { value }
+    }
+
+    // Hand the value to the visitor. {raw} is set when the target is `RESPType`,
+    // see `Deserializer::deserialize_value`.
+    fn visit<'de, V>(self, visitor: V, raw: bool) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::SimpleString(s) => visitor.visit_string(s),
+            RESPType::BigNumber(digits) if raw => visitor.visit_enum(RawFrame::BigNumber(Cow::Owned(digits))),
+            RESPType::BigNumber(digits) => visitor.visit_string(digits),
+            RESPType::Error(reply) if raw => visitor.visit_enum(RawFrame::Error(Cow::Owned(reply))),
+            RESPType::Error(reply) => Err(Error::ErrorReply(RespError::parse(&reply))),
+            RESPType::Integer(n) => visitor.visit_i64(n),
+            RESPType::UInteger(n) => visitor.visit_u64(n),
+            RESPType::Double(v) => visitor.visit_f64(v),
+            RESPType::Boolean(b) => visitor.visit_bool(b),
+            RESPType::BulkString(bytes) => visitor.visit_byte_buf(bytes),
+            RESPType::Array(arr) => visitor.visit_seq(ValueSeqAccess { iter: arr.into_iter() }),
+            RESPType::Map(pairs) => visit_pairs(pairs.into_iter().flat_map(|(k, v)| [k, v]).collect(), visitor),
+            RESPType::NullArray if raw => visitor.visit_enum(RawFrame::NullArray),
+            RESPType::Null if raw => visitor.visit_enum(RawFrame::Null),
+            RESPType::None | RESPType::NullArray | RESPType::Null => visitor.visit_none(),
+        }
+    }
+
+    // The value of an integer, a big number or a string holding an integer,
+    // None for other types.
+    fn wide_int<T>(&self) -> Result<Option<T>>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        let text = match &self.value {
+            RESPType::Integer(n) => Cow::Owned(n.to_string()),
+            RESPType::UInteger(n) => Cow::Owned(n.to_string()),
+            RESPType::SimpleString(s) | RESPType::BigNumber(s) => Cow::Borrowed(s.as_str()),
+            RESPType::BulkString(bytes) => String::from_utf8_lossy(bytes),
+            _ => return Ok(None),
+        };
+        text.parse().map(Some).map_err(|err| Error::ParseIntError { err, pos: 0 })
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self.value, RESPType::None | RESPType::NullArray | RESPType::Null)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for RESPType {
+    type Deserializer = ValueDeserializer;
+
+    fn into_deserializer(self) -> ValueDeserializer {
+        ValueDeserializer::new(self)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit(visitor, false)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::Integer(0) => visitor.visit_bool(false),
+            RESPType::Integer(1) => visitor.visit_bool(true),
+            RESPType::Integer(n) => Err(de::Error::invalid_value(Unexpected::Signed(n), &"0 or 1")),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Integers may also be held by strings, as with the byte deserializer.
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let text = match &self.value {
+            RESPType::SimpleString(s) => s.as_bytes(),
+            RESPType::BulkString(bytes) => bytes.as_slice(),
+            _ => return self.deserialize_any(visitor),
+        };
+        match std::str::from_utf8(text).ok().and_then(|s| s.parse().ok()) {
+            Some(n) => visitor.visit_i64(n),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.wide_int()? {
+            Some(n) => visitor.visit_i128(n),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.wide_int()? {
+            Some(n) => visitor.visit_u128(n),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::Integer(n) => visitor.visit_f64(n as f64),
+            RESPType::SimpleString(s) => visitor.visit_f64(parse_f64(&s)?),
+            RESPType::BulkString(bytes) => visitor.visit_f64(parse_f64(&String::from_utf8(bytes)?)?),
+            _ => self.visit(visitor, false),
+        }
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    // Bulk strings read as text must be valid UTF-8.
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::BulkString(bytes) => visitor.visit_string(String::from_utf8(bytes)?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.is_null() {
+            visitor.visit_unit()
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == RESP_TYPE_TOKEN {
+            self.visit(visitor, true)
+        } else if name == REPLY_TOKEN {
+            // `Reply` reads the frame the value would have been read from
+            visitor.visit_byte_buf(Reply::from_value(self.value)?.to_bytes())
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::BulkString(bytes) if bytes.len() == len => {
+                visitor.visit_seq(SeqDeserializer::new(bytes.into_iter()))
+            }
+            RESPType::BulkString(bytes) => {
+                let expected = format!("a bulk string of {len} bytes");
+                Err(de::Error::invalid_length(bytes.len(), &expected.as_str()))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, _: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == PAIR_TOKEN {
+            return Err(Error::Unsupported { what: "Pair outside of a sequence" });
+        }
+        self.deserialize_any(visitor)
+    }
+
+    // Maps are flat arrays of alternating keys and values.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::Array(arr) => visit_pairs(arr, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    // Unit variants are their name, other variants an array of their name
+    // followed by their content.
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            RESPType::Array(arr) if !arr.is_empty() => {
+                let mut iter = arr.into_iter();
+                let variant = iter.next().map(ValueDeserializer::new);
+                visitor.visit_enum(ValueEnumAccess { variant, rest: iter })
+            }
+            RESPType::Array(_) => Err(de::Error::invalid_length(0, &"an array starting with the variant name")),
+            RESPType::BulkString(bytes) => {
+                visitor.visit_enum(StringDeserializer::<Error>::new(String::from_utf8(bytes)?))
+            }
+            RESPType::SimpleString(name) => visitor.visit_enum(StringDeserializer::<Error>::new(name)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    // Skipping an error reply is not an error.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+// Hand the elements of a flat array of keys and values to the visitor as a map.
+fn visit_pairs<'de, V>(arr: Vec<RESPType>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    if !arr.len().is_multiple_of(2) {
+        return Err(de::Error::invalid_length(arr.len(), &"an even number of elements"));
+    }
+    visitor.visit_map(ValueMapAccess { iter: arr.into_iter(), value: None })
+}
+
+// Elements left unread are dropped, as the byte deserializer skips them.
+struct ValueSeqAccess {
+    iter: vec::IntoIter<RESPType>,
+}
+
+impl<'de> SeqAccess<'de> for ValueSeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some(value) = self.iter.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(ElementDeserializer { value, rest: &mut self.iter }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+// An element of an array, read as a `ValueDeserializer` unless it is a `Pair`,
+// which takes the element following it as its value.
+struct ElementDeserializer<'a> {
+    value: RESPType,
+    rest: &'a mut vec::IntoIter<RESPType>,
+}
+
+// Deserialize methods taking only a visitor, forwarded to `ValueDeserializer`.
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value>
+        where
+            V: Visitor<'de>,
+        {
+            ValueDeserializer::new(self.value).$method(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ElementDeserializer<'_> {
+    type Error = Error;
+
+    forward_to_value! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit deserialize_seq
+        deserialize_map deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name != PAIR_TOKEN {
+            return ValueDeserializer::new(self.value).deserialize_tuple_struct(name, len, visitor);
+        }
+        let Some(value) = self.rest.next() else {
+            return Err(de::Error::invalid_length(1, &"a key and a value"));
+        };
+        visitor.visit_seq(ValueSeqAccess { iter: vec![self.value, value].into_iter() })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_enum(name, variants, visitor)
+    }
+}
+
+struct ValueMapAccess {
+    iter: vec::IntoIter<RESPType>,
+    value: Option<RESPType>,
+}
+
+impl<'de> MapAccess<'de> for ValueMapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some(key) = self.iter.next() else {
+            return Ok(None);
+        };
+        self.value = self.iter.next();
+        seed.deserialize(ValueDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value)),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len() / 2)
+    }
+}
+
+struct ValueEnumAccess {
+    variant: Option<ValueDeserializer>,
+    rest: vec::IntoIter<RESPType>,
+}
+
+impl ValueEnumAccess {
+    fn expect_len(&self, len: usize, expected: &str) -> Result<()> {
+        if self.rest.len() != len {
+            return Err(de::Error::invalid_length(self.rest.len(), &expected));
+        }
+        Ok(())
+    }
+}
+
+impl<'de> EnumAccess<'de> for ValueEnumAccess {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = self.variant.take().expect("variant is read once");
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueEnumAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.expect_len(0, "no elements after a unit variant")
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.expect_len(1, "one element after a newtype variant")?;
+        seed.deserialize(ValueDeserializer::new(self.rest.next().unwrap_or(RESPType::None)))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.expect_len(len, "as many elements as the tuple variant has fields")?;
+        visitor.visit_seq(ValueSeqAccess { iter: self.rest })
+    }
+
+    fn struct_variant<V>(self, _: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visit_pairs(self.rest.collect(), visitor)
+    }
+}
+
+/// Serializer building a [`RESPType`] tree, see [`to_value`].
+///
+/// Values are built as the default `Serializer` writes them: strings are bulk
+/// strings, booleans integers, nulls null bulk strings, and maps and structs
+/// flat arrays of keys and values.
+#[derive(Clone, Copy)]
+pub struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = RESPType;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeArray;
+    type SerializeStruct = SerializeArray;
+    type SerializeStructVariant = SerializeArray;
+
+    fn serialize_bool(self, v: bool) -> Result<RESPType> {
+        Ok(RESPType::Integer(i64::from(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RESPType> {
+        Ok(RESPType::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RESPType> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RESPType> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => Ok(RESPType::UInteger(v)),
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RESPType> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.serialize_str(&v.to_string()),
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RESPType> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.serialize_str(&v.to_string()),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RESPType> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RESPType> {
+        Ok(RESPType::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RESPType> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RESPType> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RESPType> {
+        Ok(RESPType::BulkString(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<RESPType> {
+        Ok(RESPType::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RESPType> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<RESPType> {
+        match name {
+            NULL_BULK_TOKEN => Ok(RESPType::None),
+            NULL_ARRAY_TOKEN => Ok(RESPType::NullArray),
+            NULL_TOKEN => Ok(RESPType::Null),
+            _ => self.serialize_unit(),
+        }
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<RESPType> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        newtype_value(name, value.serialize(self)?)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(RESPType::Array(vec![self.serialize_str(variant)?, value.serialize(self)?]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray> {
+        Ok(SerializeArray { elements: Vec::with_capacity(len.unwrap_or(0)), pairs: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<SerializeArray> {
+        if name == PAIR_TOKEN {
+            return Err(Error::Unsupported { what: "Pair outside of a sequence of pairs" });
+        }
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray> {
+        SerializeArray::variant(variant, len + 1)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeArray> {
+        self.serialize_seq(len.map(|len| len * 2))
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len * 2))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray> {
+        SerializeArray::variant(variant, len * 2 + 1)
+    }
+}
+
+// The value of a newtype struct named {name}, for the names this crate uses
+// to mark the RESP type a string is written as, and the encoded frame of a `Reply`.
+fn newtype_value(name: &'static str, value: RESPType) -> Result<RESPType> {
+    match (name, value) {
+        (REPLY_TOKEN, RESPType::BulkString(frame)) => crate::from_slice(&frame),
+        (SIMPLE_STRING_TOKEN, RESPType::BulkString(s)) => Ok(RESPType::SimpleString(String::from_utf8(s)?)),
+        (ERROR_TOKEN, RESPType::BulkString(s)) => Ok(RESPType::Error(String::from_utf8(s)?)),
+        (BIG_NUMBER_TOKEN, RESPType::BulkString(s)) => Ok(RESPType::BigNumber(String::from_utf8(s)?)),
+        (UINTEGER_TOKEN, RESPType::BulkString(s)) => {
+            let digits = String::from_utf8(s)?;
+            digits.parse().map(RESPType::UInteger).map_err(|err| Error::ParseIntError { err, pos: 0 })
+        }
+        (_, value) => Ok(value),
+    }
+}
+
+// Serializer of an element of a sequence, which unlike `ValueSerializer`
+// accepts a `Pair` and sets {pair} when given one.
+struct ElementSerializer<'a> {
+    pair: &'a mut bool,
+}
+
+impl ser::Serializer for ElementSerializer<'_> {
+    type Ok = RESPType;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeArray;
+    type SerializeStruct = SerializeArray;
+    type SerializeStructVariant = SerializeArray;
+
+    fn serialize_bool(self, v: bool) -> Result<RESPType> {
+        ValueSerializer.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RESPType> {
+        ValueSerializer.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RESPType> {
+        ValueSerializer.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RESPType> {
+        ValueSerializer.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RESPType> {
+        ValueSerializer.serialize_i64(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RESPType> {
+        ValueSerializer.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RESPType> {
+        ValueSerializer.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RESPType> {
+        ValueSerializer.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RESPType> {
+        ValueSerializer.serialize_u64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RESPType> {
+        ValueSerializer.serialize_i128(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RESPType> {
+        ValueSerializer.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RESPType> {
+        ValueSerializer.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RESPType> {
+        ValueSerializer.serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<RESPType> {
+        ValueSerializer.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RESPType> {
+        ValueSerializer.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RESPType> {
+        ValueSerializer.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<RESPType> {
+        ValueSerializer.serialize_none()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RESPType> {
+        ValueSerializer.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<RESPType> {
+        ValueSerializer.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(self, name: &'static str, index: u32, variant: &'static str) -> Result<RESPType> {
+        ValueSerializer.serialize_unit_variant(name, index, variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        newtype_value(name, value.serialize(self)?)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        ValueSerializer.serialize_newtype_variant(name, index, variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray> {
+        ValueSerializer.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray> {
+        ValueSerializer.serialize_tuple(len)
+    }
+
+    // A `Pair` is built as an array of its key and value, which the sequence
+    // holding it then flattens.
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<SerializeArray> {
+        if name == PAIR_TOKEN {
+            *self.pair = true;
+            return ValueSerializer.serialize_seq(Some(2));
+        }
+        ValueSerializer.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray> {
+        ValueSerializer.serialize_tuple_variant(name, index, variant, len)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeArray> {
+        ValueSerializer.serialize_map(len)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeArray> {
+        ValueSerializer.serialize_struct(name, len)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray> {
+        ValueSerializer.serialize_struct_variant(name, index, variant, len)
+    }
+}
+
+/// Array being built by [`ValueSerializer`], for every compound type.
+pub struct SerializeArray {
+    elements: Vec<RESPType>,
+    // Whether the elements of a sequence are `Pair`s, once the first is known.
+    pairs: Option<bool>,
+}
+
+impl SerializeArray {
+    // An array starting with the name of a variant.
+    fn variant(variant: &'static str, len: usize) -> Result<SerializeArray> {
+        let mut elements = Vec::with_capacity(len);
+        elements.push(ser::Serializer::serialize_str(ValueSerializer, variant)?);
+        Ok(SerializeArray { elements, pairs: None })
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.elements.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    // Push an element of a sequence, a `Pair` as two elements. Either every
+    // element of the sequence is a `Pair` or none is.
+    fn push_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut pair = false;
+        let element = value.serialize(ElementSerializer { pair: &mut pair })?;
+        if *self.pairs.get_or_insert(pair) != pair {
+            return Err(Error::Unsupported { what: "Pair outside of a sequence of pairs" });
+        }
+        match element {
+            RESPType::Array(pair) if self.pairs == Some(true) => self.elements.extend(pair),
+            element => self.elements.push(element),
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<RESPType> {
+        Ok(RESPType::Array(self.elements))
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push_element(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeMap for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeStruct for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key)?;
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeArray {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key)?;
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeArray::end(self)
+    }
+}
+
+#[cfg(test)]
+mod value_test {
+    use crate::{from_slice, from_value, to_value, to_vec, RESPType, Result};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Role {
+        Master,
+        Replica { offset: i64 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Node {
+        id: String,
+        port: u16,
+        primary: bool,
+        role: Role,
+        slots: Vec<(i64, i64)>,
+        tags: BTreeMap<String, i64>,
+        replica_of: Option<String>,
+        raw: RESPType,
+    }
+
+    fn nodes() -> Vec<Node> {
+        vec![
+            Node {
+                id: "a1".to_owned(),
+                port: 6379,
+                primary: true,
+                role: Role::Master,
+                slots: vec![(0, 5460), (10923, 16383)],
+                tags: BTreeMap::from([("dc".to_owned(), 1), ("rack".to_owned(), 7)]),
+                replica_of: None,
+                raw: RESPType::Error("ERR busy".to_owned()),
+            },
+            Node {
+                id: "b2".to_owned(),
+                port: 6380,
+                primary: false,
+                role: Role::Replica { offset: 1024 },
+                slots: vec![],
+                tags: BTreeMap::new(),
+                replica_of: Some("a1".to_owned()),
+                raw: RESPType::Array(vec![RESPType::ok(), RESPType::NullArray]),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let nodes = nodes();
+        let value = to_value(&nodes)?;
+        assert_eq!(value, from_slice::<RESPType>(&to_vec(&nodes)?)?);
+        assert_eq!(from_value::<Vec<Node>>(value.clone())?, nodes);
+        assert_eq!(from_value::<RESPType>(value.clone())?, value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_as_bytes() -> Result<()> {
+        let reply = RESPType::Array(vec![
+            RESPType::SimpleString("42".to_owned()),
+            RESPType::BulkString(b"7".to_vec()),
+            RESPType::Integer(-1),
+            RESPType::None,
+        ]);
+        let frame = to_vec(&reply)?;
+        assert_eq!(from_value::<Vec<Option<i64>>>(reply.clone())?, from_slice::<Vec<Option<i64>>>(&frame)?);
+        assert_eq!(from_value::<(String, String)>(reply.clone())?, from_slice::<(String, String)>(&frame)?);
+        let err = RESPType::Error("WRONGTYPE not a list".to_owned());
+        assert!(matches!(from_value::<Vec<i64>>(err.clone()), Err(crate::Error::ErrorReply(e)) if e.code == "WRONGTYPE"));
+        assert!(matches!(from_slice::<Vec<i64>>(&to_vec(&err)?), Err(crate::Error::ErrorReply(e)) if e.code == "WRONGTYPE"));
+        Ok(())
+    }
+}
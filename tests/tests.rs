@@ -1,3 +1,210 @@
 #[cfg(test)]
 mod test {
-}
\ No newline at end of file
+    use proptest::prelude::*;
+    use serde_resp::{
+        from_bufread, from_reader_with, from_slice, from_slice_with, to_vec, to_vec_with, DeserializerBuilder, Limits, Parser,
+        RESPType, Reply, SerializerBuilder,
+    };
+    use serde_resp::error::ErrorKind;
+    use serde_resp::helpers::debug_frame;
+    use serde::{Serialize, Serializer};
+
+    // Simple strings and errors may hold any text but '\r' and '\n'.
+    fn line() -> impl Strategy<Value = String> {
+        "[^\r\n]{0,16}"
+    }
+
+    fn resp_value() -> impl Strategy<Value = RESPType> {
+        let leaf = prop_oneof![
+            line().prop_map(RESPType::SimpleString),
+            line().prop_map(RESPType::Error),
+            any::<i64>().prop_map(RESPType::Integer),
+            prop::collection::vec(any::<u8>(), 0..32).prop_map(RESPType::BulkString),
+            Just(RESPType::None),
+            Just(RESPType::NullArray),
+            Just(RESPType::Null),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop::collection::vec(inner, 0..8).prop_map(RESPType::Array)
+        })
+    }
+
+    // Wire frames built by hand, independently of the serializer.
+    fn resp_frame() -> impl Strategy<Value = Vec<u8>> {
+        let leaf = prop_oneof![
+            line().prop_map(|s| format!("+{s}\r\n").into_bytes()),
+            line().prop_map(|s| format!("-{s}\r\n").into_bytes()),
+            any::<i64>().prop_map(|i| format!(":{i}\r\n").into_bytes()),
+            prop::collection::vec(any::<u8>(), 0..32).prop_map(|payload| {
+                let mut frame = format!("${}\r\n", payload.len()).into_bytes();
+                frame.extend_from_slice(&payload);
+                frame.extend_from_slice(b"\r\n");
+                frame
+            }),
+            Just(b"$-1\r\n".to_vec()),
+            Just(b"*-1\r\n".to_vec()),
+            Just(b"_\r\n".to_vec()),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop::collection::vec(inner, 0..8).prop_map(|elements| {
+                let mut frame = format!("*{}\r\n", elements.len()).into_bytes();
+                for element in elements {
+                    frame.extend_from_slice(&element);
+                }
+                frame
+            })
+        })
+    }
+
+    // Writes every array of a value as a sequence of unknown length, as
+    // `collect_seq` does over a filtered iterator.
+    struct UnsizedArrays<'a>(&'a RESPType);
+
+    impl Serialize for UnsizedArrays<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.0 {
+                RESPType::Array(elements) => {
+                    serializer.collect_seq(elements.iter().filter(|_| true).map(UnsizedArrays))
+                }
+                other => other.serialize(serializer),
+            }
+        }
+    }
+
+    // Shrunk counterexamples found against the str based implementation.
+    #[test]
+    fn round_trip_regressions() {
+        for frame in [&b"*-1\r\n"[..], b"*1\r\n*-1\r\n", b"$1\r\n\x80\r\n"] {
+            let value = from_slice::<RESPType>(frame).unwrap();
+            assert_eq!(to_vec(&value).unwrap(), frame);
+        }
+        for value in [RESPType::SimpleString("\r".to_owned()), RESPType::Error("\n".to_owned())] {
+            assert!(to_vec(&value).is_err());
+        }
+        assert!(from_slice::<RESPType>(b"+\n\r\n").is_err());
+    }
+
+    // Every `*.resp` file in tests/corpus holds exactly one frame captured from a
+    // real server; it must parse and serialize back to the same bytes.
+    #[test]
+    fn corpus_round_trip() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        let mut checked = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() != Some("resp".as_ref()) {
+                continue;
+            }
+            let frame = std::fs::read(&path).unwrap();
+            let value = from_slice::<RESPType>(&frame)
+                .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+            assert_eq!(to_vec(&value).unwrap(), frame, "{}", path.display());
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+
+    // A `Reply` is forwarded byte for byte, whatever the kind of frame.
+    #[test]
+    fn proxy_round_trip() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        let mut checked = 0;
+        for dir in [root.clone(), root.join("resp3")] {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension() != Some("resp".as_ref()) {
+                    continue;
+                }
+                let frame = std::fs::read(&path).unwrap();
+                let reply = from_slice::<Reply>(&frame).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+                assert_eq!(to_vec(&reply).unwrap(), frame, "{}", path.display());
+                let mut reader = frame.as_slice();
+                assert_eq!(from_bufread::<_, Reply>(&mut reader).unwrap(), reply, "{}", path.display());
+                checked += 1;
+            }
+        }
+        assert!(checked >= 29);
+    }
+
+    // One `Limits` value must be enforced the same way on every path.
+    #[test]
+    fn shared_limits() {
+        let mut limits = Limits::strict();
+        limits.set_max_bulk_len(4).set_max_depth(2);
+        let de = DeserializerBuilder::new().with_limits(limits);
+        let ser = SerializerBuilder::new().with_limits(limits);
+
+        let long = RESPType::BulkString(b"hello".to_vec());
+        let frame = to_vec(&long).unwrap();
+        assert!(from_slice_with::<RESPType>(&frame, &de).is_err());
+        let mut parser = Parser::with_limits(limits);
+        parser.feed(&frame);
+        assert!(parser.next_value::<RESPType>().is_err());
+        assert!(to_vec_with(&long, &ser).is_err());
+
+        let deep = RESPType::Array(vec![RESPType::Array(vec![RESPType::Array(vec![])])]);
+        let frame = to_vec(&deep).unwrap();
+        assert!(from_slice_with::<RESPType>(&frame, &de).is_err());
+        let mut parser = Parser::with_limits(limits);
+        parser.feed(&frame);
+        assert!(parser.next_value::<RESPType>().is_err());
+        assert!(to_vec_with(&deep, &ser).is_err());
+
+        // frame size, element budget and array length, which only apply to reading
+        let mut limits = Limits::default();
+        limits.set_max_frame_size(64).set_max_elements(8).set_max_array_len(6);
+        let de = DeserializerBuilder::new().with_limits(limits);
+        let wide = to_vec(&RESPType::Array(vec![RESPType::Integer(1); 7])).unwrap();
+        let many = to_vec(&RESPType::Array(vec![RESPType::Array(vec![RESPType::Integer(1); 5]); 2])).unwrap();
+        let large = to_vec(&RESPType::BulkString(vec![b'x'; 64])).unwrap();
+        let long_line = [&b"+"[..], &[b'x'; 100], b"\r\n"].concat();
+        for (frame, kind) in [
+            (&wide, ErrorKind::ArrayTooLarge),
+            (&many, ErrorKind::TooManyElements),
+            (&large, ErrorKind::FrameTooLarge),
+            (&long_line, ErrorKind::FrameTooLarge),
+        ] {
+            assert!(from_slice_with::<RESPType>(frame, &de).is_err_and(|err| err.kind() == kind));
+            assert!(from_reader_with::<_, RESPType>(&mut frame.as_slice(), &de).is_err_and(|err| err.kind() == kind));
+            let mut parser = Parser::with_limits(limits);
+            parser.feed(frame);
+            assert!(parser.next_value::<RESPType>().is_err_and(|err| err.kind() == kind));
+        }
+
+        let fits = RESPType::Array(vec![RESPType::BulkString(b"hell".to_vec())]);
+        let frame = to_vec_with(&fits, &ser).unwrap();
+        assert_eq!(from_slice_with::<RESPType>(&frame, &de).unwrap(), fits);
+        let mut parser = Parser::with_limits(limits);
+        parser.feed(&frame);
+        assert_eq!(parser.next_value::<RESPType>().unwrap(), Some(fits));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(10_000))]
+
+        #[test]
+        fn value_round_trip(value in resp_value()) {
+            let frame = to_vec(&value).unwrap();
+            prop_assert_eq!(from_slice::<RESPType>(&frame).unwrap(), value);
+        }
+
+        #[test]
+        fn unsized_round_trip(value in resp_value()) {
+            let frame = to_vec(&UnsizedArrays(&value)).unwrap();
+            prop_assert_eq!(&frame, &to_vec(&value).unwrap());
+            prop_assert_eq!(from_slice::<RESPType>(&frame).unwrap(), value);
+        }
+
+        #[test]
+        fn frame_round_trip(frame in resp_frame()) {
+            let value = from_slice::<RESPType>(&frame).unwrap();
+            prop_assert_eq!(to_vec(&value).unwrap(), frame);
+        }
+
+        #[test]
+        fn debug_frame_is_printable(buf in prop::collection::vec(any::<u8>(), 0..512)) {
+            let out = debug_frame(&buf);
+            prop_assert!(out.bytes().all(|b| (b' '..=b'~').contains(&b)));
+        }
+    }
+}